@@ -0,0 +1,27 @@
+//! Turns `assets/jokers.txt` into the `JokerType` enum and its tables, see
+//! the top of that file for the format.
+
+use std::{env, fs, path::Path};
+
+#[path = "build/jokers.rs"]
+mod jokers;
+
+use jokers::JOKERS;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build/jokers.rs");
+    println!("cargo:rerun-if-changed={JOKERS}");
+
+    let jokers = jokers::parse(&fs::read_to_string(JOKERS).unwrap());
+    for joker in &jokers {
+        if let Some(texture) = &joker.texture {
+            println!("cargo:rerun-if-changed={texture}");
+        }
+    }
+
+    let out = env::var("OUT_DIR").unwrap();
+    let out = Path::new(&out);
+    fs::write(out.join("jokers.rs"), jokers::generate(&jokers)).unwrap();
+    fs::write(out.join("emblems.rs"), jokers::emblems(&jokers)).unwrap();
+}
@@ -0,0 +1,649 @@
+//! Parser and code generator for `assets/jokers.txt`, see the top of that
+//! file for the format. Lives outside of build.rs so main.rs can pull it in
+//! for the tests.
+
+use std::fmt::Write as _;
+
+pub const JOKERS: &str = "assets/jokers.txt";
+
+/// Moments a joker can react to with `on`, `Trigger` in joker_effects.rs
+const EVENTS: [&str; 9] = [
+    "HandPlayed",
+    "HandScored",
+    "Discard",
+    "BlindSelected",
+    "RoundEnd",
+    "Reroll",
+    "CardAdded",
+    "JokerSold",
+    "ConsumableUsed",
+];
+
+/// Characters a description line fits on screen, the joker info panel is
+/// 8 pixels a character plus 4 of border
+const DESCRIPTION_WIDTH: usize = 19;
+/// Room kept for the value filled into a `{}`
+const SHOWS_WIDTH: usize = 4;
+
+#[derive(Default)]
+pub struct Joker {
+    pub variant: String,
+    pub comment: Vec<String>,
+    pub name: Option<String>,
+    pub rarity: Option<String>,
+    pub cost: Option<String>,
+    pub description: Option<String>,
+    pub shows: Option<String>,
+    pub texture: Option<String>,
+    pub limit: Option<String>,
+    pub ability: Option<(String, String)>,
+    pub events: Vec<Rule>,
+    pub growth: Option<String>,
+    pub emblem: Vec<String>,
+    pub ink: Option<String>,
+    pub scary: bool,
+}
+
+/// One `on = <Event> [if <condition>] then <action>` line
+#[derive(Debug, PartialEq)]
+pub struct Rule {
+    pub event: String,
+    pub condition: Option<String>,
+    pub action: String,
+}
+
+pub fn parse(data: &str) -> Vec<Joker> {
+    let mut jokers: Vec<Joker> = Vec::new();
+    let mut comment = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        let fail = |reason: &str| -> ! { panic!("{JOKERS}:{}: {reason}", i + 1) };
+
+        if line.is_empty() {
+            comment.clear();
+        } else if let Some(text) = line.strip_prefix('#') {
+            comment.push(text.trim().to_string());
+        } else if let Some(variant) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if jokers.iter().any(|joker| joker.variant == variant) {
+                fail("joker declared twice");
+            }
+            jokers.push(Joker {
+                variant: variant.to_string(),
+                comment: std::mem::take(&mut comment),
+                ..Default::default()
+            });
+        } else {
+            let Some((key, value)) = line.split_once('=') else { fail("expected `key = value`") };
+            let Some(joker) = jokers.last_mut() else { fail("value outside of a joker") };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "name" => joker.name = Some(value),
+                "rarity" => joker.rarity = Some(value),
+                "cost" => joker.cost = Some(value),
+                "description" => {
+                    let width = |line: &str| line.len() + line.matches("{}").count() * (SHOWS_WIDTH - 2);
+                    if let Some(line) = value.split("\\n").find(|line| width(line) > DESCRIPTION_WIDTH) {
+                        fail(&format!("description line `{line}` is over {DESCRIPTION_WIDTH} characters"));
+                    }
+                    joker.description = Some(value);
+                }
+                "shows" => joker.shows = Some(value),
+                "texture" => joker.texture = Some(value),
+                "limit" => joker.limit = Some(value),
+                "growth" => joker.growth = Some(value),
+                "on" => joker.events.push(rule(&value).unwrap_or_else(|reason| fail(&reason))),
+                "emblem" => {
+                    for command in value.split(';') {
+                        joker.emblem.push(draw(command).unwrap_or_else(|reason| fail(&reason)));
+                    }
+                }
+                "ink" => match value.as_str() {
+                    "red" | "blue" => joker.ink = Some(value),
+                    other => fail(&format!("unknown ink `{other}`")),
+                },
+                "jester" => match value.as_str() {
+                    "scary" => joker.scary = true,
+                    other => fail(&format!("unknown jester `{other}`")),
+                },
+                ability @ ("independent" | "card" | "retrigger" | "copy") => {
+                    if joker.ability.is_some() {
+                        fail("a joker has one ability at most");
+                    }
+                    joker.ability = Some((ability.to_string(), value));
+                }
+                key => fail(&format!("unknown key `{key}`")),
+            }
+        }
+    }
+    for joker in &jokers {
+        let missing = [
+            ("name", &joker.name),
+            ("rarity", &joker.rarity),
+            ("cost", &joker.cost),
+            ("description", &joker.description),
+        ];
+        for (key, value) in missing {
+            if value.is_none() {
+                panic!("{JOKERS}: {} has no {key}", joker.variant);
+            }
+        }
+    }
+    jokers
+}
+
+fn rule(value: &str) -> Result<Rule, String> {
+    let Some((head, action)) = value.split_once(" then ") else {
+        return Err(format!("`{value}` has no `then`"));
+    };
+    let (event, condition) = match head.split_once(" if ") {
+        Some((event, condition)) => (event, Some(condition.trim().to_string())),
+        None => (head, None),
+    };
+    let event = event.trim();
+    if !EVENTS.contains(&event) {
+        return Err(format!("unknown event `{event}`"));
+    }
+    Ok(Rule { event: event.to_string(), condition, action: action.trim().to_string() })
+}
+
+/// `circle 30 55 8` to `canvas.circle([30, 55], 8);`, a `line` goes
+/// through every point after it
+fn draw(command: &str) -> Result<String, String> {
+    let mut words = command.split_whitespace();
+    let shape = words.next().unwrap_or_default();
+    let numbers: Vec<i32> = match words.map(str::parse).collect() {
+        Ok(numbers) => numbers,
+        Err(_) => return Err(format!("`{}` takes numbers", command.trim())),
+    };
+    match (shape, numbers.as_slice()) {
+        ("line", points) if points.len() >= 4 && points.len() % 2 == 0 => Ok(points
+            .chunks(2)
+            .zip(points.chunks(2).skip(1))
+            .map(|(from, to)| format!("canvas.line([{}, {}], [{}, {}]);", from[0], from[1], to[0], to[1]))
+            .collect::<Vec<_>>()
+            .join(" ")),
+        ("rect" | "fill", [x, y, w, h]) => Ok(format!("canvas.{shape}([{x}, {y}], [{w}, {h}]);")),
+        ("circle", [x, y, r]) => Ok(format!("canvas.circle([{x}, {y}], {r});")),
+        _ => Err(format!("can't draw `{}`", command.trim())),
+    }
+}
+
+/// Goes into joker/mod.rs
+pub fn generate(jokers: &[Joker]) -> String {
+    let mut out = String::new();
+    let arms = |out: &mut String, f: &dyn Fn(&Joker) -> String| {
+        for joker in jokers {
+            writeln!(out, "            JokerType::{} => {},", joker.variant, f(joker)).unwrap();
+        }
+    };
+
+    writeln!(out, "// Generated by build.rs from {JOKERS}, edit that instead").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]").unwrap();
+    writeln!(out, "pub enum JokerType {{").unwrap();
+    for joker in jokers {
+        for line in &joker.comment {
+            writeln!(out, "    // {line}").unwrap();
+        }
+        writeln!(out, "    {},", joker.variant).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "impl JokerType {{").unwrap();
+    writeln!(out, "    pub fn name(&self) -> &'static str {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| format!("{:?}", joker.name.as_ref().unwrap()));
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    pub fn cost(&self) -> u32 {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| joker.cost.clone().unwrap());
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    pub fn rarity(&self) -> Rarity {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| format!("Rarity::{}", joker.rarity.as_ref().unwrap()));
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    /// Highest the joker's counter goes, saves with more are corrupted").unwrap();
+    writeln!(out, "    pub fn limit(&self) -> u16 {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| joker.limit.clone().unwrap_or("u16::MAX".to_string()));
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    /// Suit the joker wants scored, if any").unwrap();
+    writeln!(out, "    fn suit(&self) -> Option<Suit> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| match joker.ability.as_ref().and_then(|(_, value)| value.split_once(" if suit ")) {
+        Some((_, suit)) => format!("Some(Suit::{suit})"),
+        None => "None".to_string(),
+    });
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    /// Poker hand the joker looks for in the played cards, if any").unwrap();
+    writeln!(out, "    fn contains(&self) -> Option<PokerHand> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| match joker.ability.as_ref().and_then(|(_, value)| value.split_once(" if contains ")) {
+        Some((_, hand)) => format!("Some(PokerHand::{hand})"),
+        None => "None".to_string(),
+    });
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    /// Hand drawn 2bpp art").unwrap();
+    writeln!(out, "    fn art(&self) -> Option<&'static [u8; 1200]> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| match &joker.texture {
+        Some(path) => format!("Some(include_bytes!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/{path}\")))"),
+        None => "None".to_string(),
+    });
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    #[allow(unused_variables)]").unwrap();
+    writeln!(out, "    fn proc(&self) -> ProcType {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| ability(joker));
+    writeln!(out, "        }}\n    }}").unwrap();
+
+    writeln!(out, "    /// Events the joker reacts to outside of scoring, scaling jokers grow").unwrap();
+    writeln!(out, "    /// their counter here").unwrap();
+    writeln!(out, "    #[allow(unused_variables)]").unwrap();
+    writeln!(out, "    fn events(&self) -> (BitFlags<Trigger>, EventProc) {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    arms(&mut out, &|joker| events(joker));
+    writeln!(out, "        }}\n    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "impl Joker {{").unwrap();
+    writeln!(out, "    /// What the joker does, with the current value for scaling ones").unwrap();
+    writeln!(out, "    pub fn description<'a>(&self, buf: &'a mut [u8]) -> &'a str {{").unwrap();
+    writeln!(out, "        let args = match self.kind {{").unwrap();
+    for joker in jokers {
+        let description = joker.description.as_ref().unwrap();
+        let args = match &joker.shows {
+            Some(shows) => format!(", {}", expression(&joker.variant, shows, "self.", false)),
+            None => String::new(),
+        };
+        writeln!(out, "            JokerType::{} => format_args!(\"{description}\"{args}),", joker.variant).unwrap();
+    }
+    writeln!(out, "        }};").unwrap();
+    writeln!(out, "        format_no_std::show(buf, args).unwrap_or(self.kind.name())").unwrap();
+    writeln!(out, "    }}").unwrap();
+
+    writeln!(out, "    /// Sell value the joker built up on top of half its price").unwrap();
+    writeln!(out, "    fn growth(&self) -> u32 {{").unwrap();
+    writeln!(out, "        match self.kind {{").unwrap();
+    for joker in jokers {
+        let growth = match &joker.growth {
+            Some(growth) => expression(&joker.variant, growth, "self.", false),
+            None => "0".to_string(),
+        };
+        writeln!(out, "            JokerType::{} => {growth},", joker.variant).unwrap();
+    }
+    writeln!(out, "        }}\n    }}\n}}").unwrap();
+
+    out
+}
+
+/// Goes into joker/textures.rs
+pub fn emblems(jokers: &[Joker]) -> String {
+    let mut out = String::new();
+    writeln!(out, "// Generated by build.rs from {JOKERS}, edit that instead").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "/// Drawing under the jester telling jokers without art apart").unwrap();
+    writeln!(out, "fn emblem(kind: JokerType, canvas: &mut Canvas) {{").unwrap();
+    writeln!(out, "    match kind {{").unwrap();
+    for joker in jokers.iter().filter(|joker| !joker.emblem.is_empty()) {
+        writeln!(out, "        JokerType::{} => {{", joker.variant).unwrap();
+        for command in &joker.emblem {
+            writeln!(out, "            {command}").unwrap();
+        }
+        writeln!(out, "        }}").unwrap();
+    }
+    writeln!(out, "        _ => (),").unwrap();
+    writeln!(out, "    }}\n}}").unwrap();
+
+    writeln!(out, "fn ink(kind: JokerType) -> DrawIndex {{").unwrap();
+    writeln!(out, "    match kind {{").unwrap();
+    for (ink, index) in [("red", "Third"), ("blue", "First")] {
+        let kinds: Vec<String> = jokers
+            .iter()
+            .filter(|joker| joker.ink.as_deref() == Some(ink))
+            .map(|joker| format!("JokerType::{}", joker.variant))
+            .collect();
+        if !kinds.is_empty() {
+            writeln!(out, "        {} => DrawIndex::{index},", kinds.join(" | ")).unwrap();
+        }
+    }
+    writeln!(out, "        _ => DrawIndex::Second,").unwrap();
+    writeln!(out, "    }}\n}}").unwrap();
+
+    let scary: Vec<String> = jokers
+        .iter()
+        .filter(|joker| joker.scary)
+        .map(|joker| format!("JokerType::{}", joker.variant))
+        .collect();
+    writeln!(out, "fn is_scary(kind: JokerType) -> bool {{").unwrap();
+    match scary.is_empty() {
+        true => writeln!(out, "    false").unwrap(),
+        false => writeln!(out, "    matches!(kind, {})", scary.join(" | ")).unwrap(),
+    }
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+fn ability(joker: &Joker) -> String {
+    let variant = &joker.variant;
+    let Some((kind, value)) = &joker.ability else {
+        return "ProcType::Passive".to_string();
+    };
+    match kind.as_str() {
+        "independent" => {
+            let (effect, condition) = match value.split_once(" if ") {
+                Some((effect, condition)) => (effect, Some(condition)),
+                None => (value.as_str(), None),
+            };
+            let effect = effect_of(variant, effect);
+            match condition {
+                Some(condition) => format!(
+                    "ProcType::Independent(|joker, view| ({}).then(|| {effect}))",
+                    condition_of(variant, condition),
+                ),
+                None => format!("ProcType::Independent(|joker, view| Some({effect}))"),
+            }
+        }
+        "card" => {
+            let Some((effect, condition)) = value.split_once(" if ") else {
+                panic!("{JOKERS}: {variant} needs a card condition");
+            };
+            format!(
+                "ProcType::Card(|joker, card, view| ({}).then(|| {}))",
+                card_condition(variant, condition, false),
+                effect_of(variant, effect),
+            )
+        }
+        "retrigger" => format!(
+            "ProcType::Retrigger(|joker, card, pos, view| ({}) as u8)",
+            card_condition(variant, value, true),
+        ),
+        "copy" => match value.as_str() {
+            "right" => "ProcType::Copy(CopyTarget::Right)".to_string(),
+            "leftmost" => "ProcType::Copy(CopyTarget::Leftmost)".to_string(),
+            other => panic!("{JOKERS}: {variant} can't copy `{other}`"),
+        },
+        _ => unreachable!(),
+    }
+}
+
+/// The `on` rules of a joker as a tuple of triggers and the closure
+/// running them in order
+fn events(joker: &Joker) -> String {
+    let variant = &joker.variant;
+    if joker.events.is_empty() {
+        return "(BitFlags::empty(), |_, _, _| None)".to_string();
+    }
+    let mut triggers: Vec<String> = Vec::new();
+    let mut body = String::new();
+    let mut destroys = false;
+    for rule in &joker.events {
+        let trigger = format!("Trigger::{}", rule.event);
+        let condition = match &rule.condition {
+            Some(condition) => format!("event.trigger() == {trigger} && {}", event_condition(variant, condition)),
+            None => format!("event.trigger() == {trigger}"),
+        };
+        let action = match rule.action.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["reset"] => "joker.counter = 0;".to_string(),
+            ["destroy"] => {
+                destroys = true;
+                "effect = Some(Effect::Destroy);".to_string()
+            }
+            ["counter", "+", amount] => match &joker.limit {
+                Some(_) => format!("joker.counter = joker.counter.saturating_add({amount}).min(joker.kind.limit());"),
+                None => format!("joker.counter = joker.counter.saturating_add({amount});"),
+            },
+            ["counter", "-", amount] => format!("joker.counter = joker.counter.saturating_sub({amount});"),
+            _ => panic!("{JOKERS}: {variant} has unknown action `{}`", rule.action),
+        };
+        write!(body, "\n                if {condition} {{\n                    {action}\n                }}").unwrap();
+        if !triggers.contains(&trigger) {
+            triggers.push(trigger);
+        }
+    }
+    let triggers = match triggers.as_slice() {
+        [single] => format!("{single}.into()"),
+        _ => triggers.join(" | "),
+    };
+    match destroys {
+        true => format!(
+            "({triggers}, |joker, event, view| {{\n                let mut effect = None;{body}\n                effect\n            }})"
+        ),
+        false => format!("({triggers}, |joker, event, view| {{{body}\n                None\n            }})"),
+    }
+}
+
+fn event_condition(variant: &str, condition: &str) -> String {
+    match condition.split_once(' ').unwrap_or((condition, "")) {
+        ("face", "") => "event.played().iter().any(Card::is_face)".to_string(),
+        ("no_face", "") => "!event.played().iter().any(Card::is_face)".to_string(),
+        ("planet", "") => "matches!(event, JokerEvent::ConsumableUsed(Consumable::Planet(_)))".to_string(),
+        ("contains", hand) => format!("event.contained().contains(PokerHand::{hand})"),
+        _ => expression(variant, condition, "joker.", false).replace("view.played.len()", "event.played().len()"),
+    }
+}
+
+/// `mult 4` to `Effect::mult(4)`
+fn effect_of(variant: &str, effect: &str) -> String {
+    let Some((kind, amount)) = effect.split_once(' ') else {
+        panic!("{JOKERS}: {variant} effect `{effect}` has no amount");
+    };
+    match kind {
+        "chips" | "mult" => format!("Effect::{kind}({})", expression(variant, amount, "joker.", false)),
+        "multx" => format!("Effect::multx({})", expression(variant, amount, "joker.", true)),
+        "money" => format!("Effect::Money({})", expression(variant, amount, "joker.", false)),
+        other => panic!("{JOKERS}: {variant} has unknown effect `{other}`"),
+    }
+}
+
+fn condition_of(variant: &str, condition: &str) -> String {
+    if let Some(hand) = condition.strip_prefix("contains ") {
+        return format!("view.contained.contains(PokerHand::{hand})");
+    }
+    expression(variant, condition, "joker.", false)
+}
+
+fn card_condition(variant: &str, condition: &str, retrigger: bool) -> String {
+    let mut words = condition.split_whitespace();
+    match words.next() {
+        Some("suit") => format!("card.is_suit(Suit::{})", words.next().unwrap()),
+        Some("rank") => {
+            let ranks: Vec<String> = words.map(|rank| format!("Rank::{rank}")).collect();
+            format!("card.has_rank() && matches!(card.rank(), {})", ranks.join(" | "))
+        }
+        Some("face") => "card.is_face()".to_string(),
+        Some("even") => "card.has_rank() && !card.is_face() && card.value() % 2 == 0".to_string(),
+        Some("odd") => "card.has_rank() && !card.is_face() && card.value() % 2 == 1".to_string(),
+        Some("final_hand") => "view.hands == 0".to_string(),
+        Some("first") if retrigger => "pos == 0".to_string(),
+        _ => panic!("{JOKERS}: {variant} has unknown card condition `{condition}`"),
+    }
+}
+
+/// Space separated tokens to a Rust expression, values are read off
+/// `owner` (the joker) or the game view
+fn expression(variant: &str, expression: &str, owner: &str, float: bool) -> String {
+    let float = float || expression.contains('.');
+    let ty = if float { "f32" } else { "u32" };
+    let tokens: Vec<String> = expression
+        .split_whitespace()
+        .map(|token| match token {
+            "+" | "-" | "*" | "/" | "<" | "<=" | ">" | ">=" | "==" | "!=" => token.to_string(),
+            "counter" => format!("({owner}counter as {ty})"),
+            "discards" | "hands" if owner == "joker." => format!("(view.{token} as {ty})"),
+            "played" if owner == "joker." => format!("(view.played.len() as {ty})"),
+            number if number.parse::<f32>().is_ok() => match float && !number.contains('.') {
+                true => format!("{number}.0"),
+                false => number.to_string(),
+            },
+            other => panic!("{JOKERS}: {variant} can't use `{other}` in `{expression}`"),
+        })
+        .collect();
+    let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    match tokens.iter().position(|token| matches!(*token, "<" | "<=" | ">" | ">=" | "==" | "!=")) {
+        // Casts next to a comparison keep their parentheses, `as u32 <`
+        // would read as generics
+        Some(i) => format!("{} {} {}", sum(&tokens[..i], float, false), tokens[i], sum(&tokens[i + 1..], float, false)),
+        None => sum(&tokens, float, true),
+    }
+}
+
+/// Joins the terms of a sum. Whole numbers can't go below 0, `-` saturates
+/// so a counter that outgrew its joker doesn't wrap around.
+fn sum(tokens: &[&str], float: bool, strip: bool) -> String {
+    let term = |tokens: &[&str]| match tokens {
+        // Parentheses only keep casts apart from the operators around them
+        [single] => single.trim_start_matches('(').trim_end_matches(')').to_string(),
+        _ => tokens.join(" "),
+    };
+    if float || !tokens.contains(&"-") && !tokens.contains(&"+") {
+        return match strip {
+            true => term(tokens),
+            false => tokens.join(" "),
+        };
+    }
+    let mut terms = tokens.split(|token| matches!(*token, "+" | "-"));
+    let mut out = term(terms.next().unwrap());
+    let operators = tokens.iter().filter(|token| matches!(**token, "+" | "-"));
+    for (operator, tokens) in operators.zip(terms) {
+        out = match *operator {
+            "+" => format!("{out} + {}", term(tokens)),
+            _ => format!("u32::saturating_sub({out}, {})", term(tokens)),
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JIMBO: &str = "[Jimbo]\nname = Joker\nrarity = Common\ncost = 2\ndescription = +4 Mult\n";
+
+    #[test]
+    fn parses_keys_and_comments() {
+        let jokers = parse(&format!("# The first one\n{JIMBO}independent = mult 4\nink = red\njester = scary\n"));
+        assert_eq!(jokers.len(), 1);
+        let joker = &jokers[0];
+        assert_eq!(joker.variant, "Jimbo");
+        assert_eq!(joker.comment, ["The first one"]);
+        assert_eq!(joker.name.as_deref(), Some("Joker"));
+        assert_eq!(joker.cost.as_deref(), Some("2"));
+        assert_eq!(joker.ability, Some(("independent".to_string(), "mult 4".to_string())));
+        assert_eq!(joker.ink.as_deref(), Some("red"));
+        assert!(joker.scary);
+    }
+
+    #[test]
+    fn parses_event_rules() {
+        let jokers = parse(&format!("{JIMBO}on = HandPlayed if no_face then counter + 1\non = Discard then reset\n"));
+        assert_eq!(
+            jokers[0].events,
+            [
+                Rule {
+                    event: "HandPlayed".to_string(),
+                    condition: Some("no_face".to_string()),
+                    action: "counter + 1".to_string(),
+                },
+                Rule { event: "Discard".to_string(), condition: None, action: "reset".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown event `Shuffle`")]
+    fn rejects_unknown_events() {
+        parse(&format!("{JIMBO}on = Shuffle then counter + 1\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Jimbo has no cost")]
+    fn rejects_missing_keys() {
+        parse("[Jimbo]\nname = Joker\nrarity = Common\ndescription = +4 Mult\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "jokers.txt:2: joker declared twice")]
+    fn rejects_duplicates() {
+        parse(&format!("[Jimbo]\n{JIMBO}"));
+    }
+
+    #[test]
+    #[should_panic(expected = "a joker has one ability at most")]
+    fn rejects_second_ability() {
+        parse(&format!("{JIMBO}independent = mult 4\ncard = mult 3 if face\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "assets/jokers.txt:2: description line `+{} Mult per round` is over 19 characters")]
+    fn rejects_long_description_lines() {
+        // 18 characters, but the filled in value takes more room than `{}`
+        parse("[Long]\ndescription = Fits\\n+{} Mult per round\n");
+    }
+
+    #[test]
+    fn emblems_expand_to_canvas_calls() {
+        assert_eq!(draw("circle 30 55 8").unwrap(), "canvas.circle([30, 55], 8);");
+        assert_eq!(draw(" fill 1 2 3 4 ").unwrap(), "canvas.fill([1, 2], [3, 4]);");
+        assert_eq!(
+            draw("line 0 0 1 1 2 0").unwrap(),
+            "canvas.line([0, 0], [1, 1]); canvas.line([1, 1], [2, 0]);"
+        );
+        assert!(draw("line 0 0").is_err());
+        assert!(draw("rect 1 2 3").is_err());
+        assert!(draw("circle a b c").is_err());
+    }
+
+    #[test]
+    fn effects() {
+        assert_eq!(effect_of("X", "mult 4"), "Effect::mult(4)");
+        assert_eq!(effect_of("X", "multx 2"), "Effect::multx(2.0)");
+        assert_eq!(effect_of("X", "money 3"), "Effect::Money(3)");
+    }
+
+    #[test]
+    fn expressions_saturate_and_keep_casts_apart() {
+        assert_eq!(expression("X", "100 - 5 * counter", "self.", false), "u32::saturating_sub(100, 5 * (self.counter as u32))");
+        assert_eq!(expression("X", "played <= 3", "joker.", false), "(view.played.len() as u32) <= 3");
+        assert_eq!(expression("X", "1 + 0.25 * counter", "joker.", false), "1.0 + 0.25 * (joker.counter as f32)");
+        assert_eq!(expression("X", "counter", "self.", false), "self.counter as u32");
+    }
+
+    /// Generated code with every run of whitespace down to a single space
+    fn flat(code: String) -> String {
+        code.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn events_check_their_trigger() {
+        let jokers = parse(&format!(
+            "{JIMBO}limit = 20\non = HandScored then counter + 1\non = HandScored if counter >= 20 then destroy\n"
+        ));
+        assert_eq!(
+            flat(events(&jokers[0])),
+            "(Trigger::HandScored.into(), |joker, event, view| { let mut effect = None; \
+             if event.trigger() == Trigger::HandScored { joker.counter = joker.counter.saturating_add(1).min(joker.kind.limit()); } \
+             if event.trigger() == Trigger::HandScored && (joker.counter as u32) >= 20 { effect = Some(Effect::Destroy); } \
+             effect })"
+        );
+        let jokers = parse(&format!("{JIMBO}on = HandPlayed if played == 4 then counter + 1\non = Discard then counter - 1\n"));
+        assert_eq!(
+            flat(events(&jokers[0])),
+            "(Trigger::HandPlayed | Trigger::Discard, |joker, event, view| { \
+             if event.trigger() == Trigger::HandPlayed && (event.played().len() as u32) == 4 { joker.counter = joker.counter.saturating_add(1); } \
+             if event.trigger() == Trigger::Discard { joker.counter = joker.counter.saturating_sub(1); } \
+             None })"
+        );
+    }
+}
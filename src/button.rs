@@ -1,6 +1,6 @@
 use core::fmt::Debug;
 
-use wasm4::draw::{DrawIndex, Framebuffer};
+use wasm4::{control::MouseState, draw::{DrawIndex, Framebuffer}};
 
 use crate::{gfx::Render, message::{InputHandler, Message, Writer}, MouseCompound};
 
@@ -52,17 +52,20 @@ impl InputHandler for Button {
 
         if !m.buttons.left || mouse.prev().buttons.left { return };
 
-        let left = m.x as i32 > self.start[0];
-        let right = (m.x as u32) < self.get_shape()[0] + self.start[0] as u32;
-        let bottom = m.y as i32 > self.start[1];
-        let top = (m.y as u32) < self.get_shape()[1] + self.start[1] as u32;
-        if left && right && top && bottom {
+        if self.is_hovered(m) {
             tx.write(self.onclick).ok();
         }
     }
 }
 
 impl Button {
+    pub fn is_hovered(&self, m: MouseState) -> bool {
+        let left = m.x as i32 > self.start[0];
+        let right = (m.x as u32) < self.get_shape()[0] + self.start[0] as u32;
+        let bottom = m.y as i32 > self.start[1];
+        let top = (m.y as u32) < self.get_shape()[1] + self.start[1] as u32;
+        left && right && top && bottom
+    }
     fn get_shape(&self) -> [u32; 2] {
         [(self.text.len() * 8 + 3) as u32, 11]
     }
@@ -3,7 +3,7 @@ use rand::{rngs::SmallRng, RngCore as _};
 use crate::{
     animator::{
         animation_state::AnimationState,
-        transform::{Rotate, Translate},
+        transform::{Rotate, Scale, Translate},
     },
     util::Duration,
 };
@@ -47,6 +47,18 @@ pub fn idle4() -> AnimationState {
     ], Duration::from_secs(1.0), Some(idle1))
 }
 
+pub fn shatter() -> AnimationState {
+    AnimationState::new(&[
+        Rotate::new(0.0, 30.0).into(),
+        Scale::new([1.0, 1.0], [0.0, 0.0]).into(),
+    ], Duration::from_secs(0.3), None)
+}
+pub fn appear() -> AnimationState {
+    AnimationState::new(&[
+        Scale::new([0.0, 0.0], [1.0, 1.0]).into(),
+    ], Duration::from_secs(0.3), Some(idle1))
+}
+
 pub fn proc1() -> AnimationState {
     AnimationState::new(&[
         Rotate::new(0.0, 10.0).into(),
@@ -60,5 +72,5 @@ pub fn proc2() -> AnimationState {
 pub fn proc3() -> AnimationState {
     AnimationState::new(&[
         Rotate::new(-10.0, 0.0).into(),
-    ], Duration::from_secs(0.2), None)
+    ], Duration::from_secs(0.2), Some(idle1))
 }
@@ -1,10 +1,11 @@
 use bit_reverse::ParallelReverse;
 use bitvec::{order::Msb0, slice::BitSlice, view::{AsBits, AsMutBits}};
 use heapless::Vec;
-use strum::{EnumCount, EnumIter};
+use rand::Rng;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
 use wasm4::draw::DrawIndex;
 
-use crate::gfx::texture::{Texture, TextureColors, TEXTURE_BUFFER, TEXTURE_HEIGHT, TEXTURE_WIDTH};
+use crate::gfx::texture::{Texture, TextureColors, CARD_UV0, CARD_UV1, TEXTURE_BUFFER, TEXTURE_HEIGHT, TEXTURE_WIDTH};
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, EnumIter, Clone, Copy)]
 pub enum Suit {
@@ -32,6 +33,10 @@ pub enum Rank {
 }
 
 impl Rank {
+    /// Next rank up, Aces wrap around to Twos
+    pub fn next(&self) -> Self {
+        Rank::iter().nth((*self as usize + 1) % Rank::COUNT).unwrap()
+    }
     pub fn value(&self) -> u32 {
         match self {
             Rank::Two => 2,
@@ -51,93 +56,225 @@ impl Rank {
     }
 }
 
-// #[derive(Debug, PartialEq, PartialOrd)]
-// pub enum Enhancement {
-//     Point,
-//     Mult,
-//     Stone,
-//     Glass,
-// }
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Enhancement {
+    Bonus,
+    Mult,
+    Wild,
+    Glass,
+    Steel,
+    Stone,
+    Gold,
+    Lucky,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Seal {
+    Gold,
+    Red,
+    Blue,
+    Purple,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Edition {
+    Foil,
+    Holographic,
+    Polychrome,
+    Negative,
+}
+
+// Bit fields of the packed card: (offset, width)
+const SUIT: (u16, u16) = (0, 2);
+const RANK: (u16, u16) = (2, 4);
+const ENHANCEMENT: (u16, u16) = (6, 4);
+const SEAL: (u16, u16) = (10, 3);
+const EDITION: (u16, u16) = (13, 3);
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Three bytes per card, so a full deck stays cheap to copy and fits on disk.
+/// Optional fields are stored as 0 for none, index + 1 otherwise.
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub struct Card {
-    suit: Suit,
-    rank: Rank,
-    // enhancement: Option<Enhancement>,
+    // Position-independent handle into the owning Deck
+    id: u8,
+    // Little endian, split so the struct stays byte aligned
+    bits: [u8; 2],
+}
+impl core::fmt::Debug for Card {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Card")
+            .field("id", &self.id)
+            .field("suit", &self.suit())
+            .field("rank", &self.rank())
+            .field("enhancement", &self.enhancement())
+            .field("seal", &self.seal())
+            .field("edition", &self.edition())
+            .finish()
+    }
+}
+
+/// How the hand is laid out, cards don't have a single natural order
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SortOrder {
+    /// Highest rank first, suits break ties
+    #[default]
+    Rank,
+    /// Grouped by suit, highest rank first within a suit
+    Suit,
+}
+impl SortOrder {
+    pub fn cmp(&self, left: &Card, right: &Card) -> core::cmp::Ordering {
+        let rank = right.rank().cmp(&left.rank());
+        let suit = left.suit().cmp(&right.suit());
+        match self {
+            SortOrder::Rank => rank.then(suit),
+            SortOrder::Suit => suit.then(rank),
+        }
+    }
 }
-// impl PartialOrd for Card {
-//     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-//         let suit_order = self.suit.cmp(&other.suit);
-//
-//         Some(suit_order)
-//     }
-// }
-// impl Ord for Card {
-//     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-//         self.partial_cmp(other).unwrap()
-//     }
-// }
  
 impl Card {
     pub fn new(suit: Suit, rank: Rank) -> Self {
-        Self {
-            suit,
-            rank,
-            // enhancement: None,
+        let mut card = Self { id: 0, bits: [0; 2] };
+        card.set_suit(suit);
+        card.set_rank(rank);
+        card
+    }
+    /// Card as stored on disk, see [`Card::from_bytes`]
+    pub fn to_bytes(self) -> [u8; 3] {
+        [self.id, self.bits[0], self.bits[1]]
+    }
+    /// Rejects bytes with any field out of range
+    pub fn from_bytes(bytes: [u8; 3]) -> Option<Self> {
+        let card = Self { id: bytes[0], bits: [bytes[1], bytes[2]] };
+        let valid = (card.field(RANK) as usize) < Rank::COUNT
+            && card.field(ENHANCEMENT) as usize <= Enhancement::COUNT
+            && card.field(SEAL) as usize <= Seal::COUNT
+            && card.field(EDITION) as usize <= Edition::COUNT;
+        valid.then_some(card)
+    }
+    fn field(&self, (offset, width): (u16, u16)) -> u16 {
+        (u16::from_le_bytes(self.bits) >> offset) & ((1 << width) - 1)
+    }
+    fn set_field(&mut self, (offset, width): (u16, u16), value: u16) {
+        let mask = ((1 << width) - 1) << offset;
+        let bits = (u16::from_le_bytes(self.bits) & !mask) | ((value << offset) & mask);
+        self.bits = bits.to_le_bytes();
+    }
+    fn optional<T: IntoEnumIterator>(&self, field: (u16, u16)) -> Option<T> {
+        match self.field(field) {
+            0 => None,
+            i => T::iter().nth(i as usize - 1),
         }
     }
+    pub fn random<T: Rng>(rng: &mut T) -> Self {
+        let suit = Suit::iter().nth(rng.gen_range(0..4)).unwrap();
+        let rank = Rank::iter().nth(rng.gen_range(0..Rank::COUNT)).unwrap();
+        Self::new(suit, rank)
+    }
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+    pub(super) fn set_id(&mut self, id: u8) {
+        self.id = id;
+    }
     pub fn value(&self) -> u32 {
-        self.rank.value()
+        self.rank().value()
+    }
+    /// Chips the card scores, including its enhancement
+    pub fn chips(&self) -> u32 {
+        match self.enhancement() {
+            Some(Enhancement::Stone) => 50,
+            Some(Enhancement::Bonus) => self.value() + 30,
+            _ => self.value(),
+        }
+    }
+    /// Turns this card into a copy of `other`, keeping its own id
+    pub fn copy_from(&mut self, other: &Card) {
+        self.bits = other.bits;
     }
     pub fn rank(&self) -> Rank {
-        self.rank
+        Rank::iter().nth(self.field(RANK) as usize).unwrap()
+    }
+    pub fn set_rank(&mut self, rank: Rank) {
+        self.set_field(RANK, rank as u16);
+    }
+    pub fn enhance(&mut self, enhancement: Enhancement) {
+        self.set_field(ENHANCEMENT, enhancement as u16 + 1);
+    }
+    pub fn enhancement(&self) -> Option<Enhancement> {
+        self.optional(ENHANCEMENT)
+    }
+    pub fn set_seal(&mut self, seal: Seal) {
+        self.set_field(SEAL, seal as u16 + 1);
+    }
+    pub fn seal(&self) -> Option<Seal> {
+        self.optional(SEAL)
+    }
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.set_field(EDITION, edition as u16 + 1);
+    }
+    pub fn edition(&self) -> Option<Edition> {
+        self.optional(EDITION)
     }
-    // pub fn enhance(&mut self, enhancement: Enhancement) {
-    //     self.enhancement = Some(enhancement);
-    // }
     pub fn is_face(&self) -> bool {
-        match self.rank {
-            Rank::Jack | Rank::Queen | Rank::King => true,
+        match self.rank() {
+            Rank::Jack | Rank::Queen | Rank::King => self.has_rank(),
             _ => false,
         }
     }
+    /// Stone cards keep a rank underneath, but it never counts
+    pub fn has_rank(&self) -> bool {
+        self.enhancement() != Some(Enhancement::Stone)
+    }
     pub fn texture(&self) -> [Texture; 2] {
         // let buf = &ACE_OF_SPADES;
+        let (suit, rank) = (self.suit(), self.rank());
 
         let buf = unsafe { TEXTURE_BUFFER.as_mut() }.unwrap();
         buf.fill(0);
         let bitbuf = buf.as_mut_bits::<Msb0>();
 
+        // Stone cards have no rank or suit to show
+        if self.enhancement() == Some(Enhancement::Stone) {
+            for i in 0..TEXTURE_WIDTH * TEXTURE_HEIGHT {
+                bitbuf.set(i, (i % TEXTURE_WIDTH + i / TEXTURE_WIDTH).is_multiple_of(5));
+            }
+            let colors = TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::Second]);
+            return [ Texture { buf, uv: CARD_UV0, colors },
+                     Texture { buf, uv: CARD_UV1, colors } ];
+        }
+
         font_into_buffer(
             bitbuf,
-            self.rank as usize,
+            rank as usize,
             60 + 2,
             false
         );
         font_into_buffer(
             bitbuf,
-            self.rank as usize,
+            rank as usize,
             4800 - TEXTURE_WIDTH * (CARD_FONT_HEIGHT + 1) - CARD_FONT_WIDTH - 2,
             true,
         );
         font_into_buffer(
             bitbuf,
-            self.suit as usize + 13,
+            suit as usize + 13,
             TEXTURE_WIDTH * (CARD_FONT_HEIGHT + 2) + 2,
             false,
         );
         font_into_buffer(
             bitbuf,
-            self.suit as usize + 13,
+            suit as usize + 13,
             4800 - TEXTURE_WIDTH * (CARD_FONT_HEIGHT * 2 + 2) - CARD_FONT_WIDTH - 2,
             true,
         );
 
-        if !self.is_face() && self.rank != Rank::Ace {
+        if !self.is_face() && rank != Rank::Ace {
             let x1 = 15;
             let x2 = 60 - 15 - CARD_FONT_WIDTH;
             let x3 = TEXTURE_WIDTH as usize / 2 - (CARD_FONT_WIDTH / 2);
-            let vec: Vec<(usize, bool), 10> = match self.rank {
+            let vec: Vec<(usize, bool), 10> = match rank {
                 Rank::Two => Vec::from_slice(&[
                     (x3 + 60 * 10, false),
                     (x3 + 60 * 60, true),
@@ -193,11 +330,38 @@ impl Card {
             };
             // trace("drawing pattern...");
             for (draw_idx, flip) in vec {
-                font_into_buffer(bitbuf, (self.suit as usize) + 13, draw_idx, flip);
+                font_into_buffer(bitbuf, (suit as usize) + 13, draw_idx, flip);
+            }
+        }
+
+        // Outer frame marks enhanced cards
+        if self.enhancement().is_some() {
+            for x in 0..TEXTURE_WIDTH {
+                bitbuf.set(x, true);
+                bitbuf.set(x + TEXTURE_WIDTH * (TEXTURE_HEIGHT - 1), true);
+            }
+            for y in 0..TEXTURE_HEIGHT {
+                bitbuf.set(TEXTURE_WIDTH * y, true);
+                bitbuf.set(TEXTURE_WIDTH - 1 + TEXTURE_WIDTH * y, true);
+            }
+        }
+
+        // Seal is a block on the left edge
+        if self.seal().is_some() {
+            for y in 37..43 {
+                bitbuf[3 + y * TEXTURE_WIDTH..9 + y * TEXTURE_WIDTH].fill(true);
+            }
+        }
+        // Editions get a striped top right corner
+        if self.edition().is_some() {
+            for y in 2..10 {
+                for x in TEXTURE_WIDTH - 10..TEXTURE_WIDTH - 2 {
+                    bitbuf.set(x + y * TEXTURE_WIDTH, (x + y) % 3 == 0);
+                }
             }
         }
 
-        let colors = match self.suit {
+        let colors = match suit {
             Suit::Spade | Suit::Club => TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::Second]),
             Suit::Heart | Suit::Diamond => TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::Third]),
         };
@@ -225,8 +389,24 @@ impl Card {
     }
 
     pub fn suit(&self) -> Suit {
-        self.suit
+        Suit::iter().nth(self.field(SUIT) as usize).unwrap()
+    }
+    /// Wild cards count as every suit, Stone cards as none
+    pub fn is_suit(&self, suit: Suit) -> bool {
+        match self.enhancement() {
+            Some(Enhancement::Wild) => true,
+            Some(Enhancement::Stone) => false,
+            _ => self.suit() == suit,
+        }
     }
+    pub fn set_suit(&mut self, suit: Suit) {
+        self.set_field(SUIT, suit as u16);
+    }
+}
+
+/// Draws a suit glyph with its top left corner at `draw_idx`
+pub(crate) fn suit_into_buffer(bitbuf: &mut BitSlice<u8, Msb0>, suit: Suit, draw_idx: usize) {
+    font_into_buffer(bitbuf, suit as usize + 13, draw_idx, false);
 }
 
 fn font_into_buffer(bitbuf: &mut BitSlice<u8, Msb0>, font_idx: usize, draw_idx: usize, flip: bool) {
@@ -271,3 +451,57 @@ impl From<&[u8]> for CardChar {
         Self(buf)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut card = Card::new(Suit::Diamond, Rank::Queen);
+        card.set_id(200);
+        card.enhance(Enhancement::Lucky);
+        card.set_seal(Seal::Purple);
+        card.set_edition(Edition::Negative);
+
+        let loaded = Card::from_bytes(card.to_bytes()).unwrap();
+        assert_eq!(loaded, card);
+        assert_eq!(loaded.suit(), Suit::Diamond);
+        assert_eq!(loaded.rank(), Rank::Queen);
+        assert_eq!(loaded.enhancement(), Some(Enhancement::Lucky));
+        assert_eq!(loaded.seal(), Some(Seal::Purple));
+        assert_eq!(loaded.edition(), Some(Edition::Negative));
+    }
+
+    #[test]
+    fn from_bytes_rejects_out_of_range_fields() {
+        // Rank 13 is one past the Ace
+        let bits = (13u16 << RANK.0).to_le_bytes();
+        assert!(Card::from_bytes([0, bits[0], bits[1]]).is_none());
+
+        let bits = ((Seal::COUNT as u16 + 1) << SEAL.0).to_le_bytes();
+        assert!(Card::from_bytes([0, bits[0], bits[1]]).is_none());
+    }
+
+    #[test]
+    fn sort_orders() {
+        let mut cards = [
+            Card::new(Suit::Heart, Rank::Two),
+            Card::new(Suit::Spade, Rank::Ten),
+            Card::new(Suit::Heart, Rank::Ace),
+            Card::new(Suit::Spade, Rank::Two),
+        ];
+        let order = |cards: [Card; 4]| cards.map(|card| (card.suit(), card.rank()));
+
+        cards.sort_by(|left, right| SortOrder::Rank.cmp(left, right));
+        assert_eq!(
+            order(cards),
+            [(Suit::Heart, Rank::Ace), (Suit::Spade, Rank::Ten), (Suit::Spade, Rank::Two), (Suit::Heart, Rank::Two)]
+        );
+        cards.sort_by(|left, right| SortOrder::Suit.cmp(left, right));
+        assert_eq!(
+            order(cards),
+            [(Suit::Spade, Rank::Ten), (Suit::Spade, Rank::Two), (Suit::Heart, Rank::Ace), (Suit::Heart, Rank::Two)]
+        );
+    }
+}
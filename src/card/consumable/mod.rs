@@ -0,0 +1,171 @@
+use core::ops::RangeInclusive;
+
+use heapless::Vec;
+use strum::IntoEnumIterator;
+
+use super::{joker::Rarity, poker::PokerHand, state::CardData, Enhancement, Rank, Seal, Suit};
+use crate::gfx::texture::Canvas;
+
+mod tarot;
+mod planet;
+mod spectral;
+
+pub use tarot::*;
+pub use planet::*;
+pub use spectral::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Consumable {
+    Tarot(Tarot),
+    Planet(Planet),
+    Spectral(Spectral),
+}
+
+impl Consumable {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Consumable::Tarot(tarot) => tarot.name(),
+            Consumable::Planet(planet) => planet.name(),
+            Consumable::Spectral(spectral) => spectral.name(),
+        }
+    }
+    /// How many hand cards have to be selected for the consumable to be used
+    pub fn selection(&self) -> RangeInclusive<usize> {
+        match self {
+            Consumable::Tarot(tarot) => tarot.selection(),
+            Consumable::Planet(_) => 0..=5,
+            Consumable::Spectral(spectral) => spectral.selection(),
+        }
+    }
+    pub fn cost(&self) -> u32 {
+        match self {
+            Consumable::Tarot(_) | Consumable::Planet(_) => 3,
+            Consumable::Spectral(_) => 4,
+        }
+    }
+    pub fn sell_value(&self) -> u32 {
+        (self.cost() / 2).max(1)
+    }
+    pub fn effects(&self) -> Vec<ConsumableEffect, 2> {
+        match self {
+            Consumable::Tarot(tarot) => Vec::from_slice(&[tarot.effect()]).unwrap(),
+            Consumable::Planet(planet) => Vec::from_slice(&[planet.effect()]).unwrap(),
+            Consumable::Spectral(spectral) => spectral.effects(),
+        }
+    }
+    /// Consumable as stored on disk, its kind then its index in the kind
+    pub fn to_bytes(self) -> [u8; 2] {
+        match self {
+            Consumable::Tarot(tarot) => [0, tarot as u8],
+            Consumable::Planet(planet) => [1, planet as u8],
+            Consumable::Spectral(spectral) => [2, spectral as u8],
+        }
+    }
+    /// Rejects unknown kinds and indices
+    pub fn from_bytes(bytes: [u8; 2]) -> Option<Self> {
+        let index = bytes[1] as usize;
+        match bytes[0] {
+            0 => Tarot::iter().nth(index).map(Consumable::Tarot),
+            1 => Planet::iter().nth(index).map(Consumable::Planet),
+            2 => Spectral::iter().nth(index).map(Consumable::Spectral),
+            _ => None,
+        }
+    }
+}
+
+impl From<Consumable> for CardData {
+    fn from(value: Consumable) -> Self {
+        match value {
+            Consumable::Tarot(tarot) => CardData::Tarot(tarot),
+            Consumable::Planet(planet) => CardData::Planet(planet),
+            Consumable::Spectral(spectral) => CardData::Spectral(spectral),
+        }
+    }
+}
+
+impl CardData {
+    pub fn consumable(&self) -> Option<Consumable> {
+        match self {
+            CardData::Tarot(tarot) => Some(Consumable::Tarot(*tarot)),
+            CardData::Planet(planet) => Some(Consumable::Planet(*planet)),
+            CardData::Spectral(spectral) => Some(Consumable::Spectral(*spectral)),
+            _ => None,
+        }
+    }
+}
+
+/// What using a consumable does, applied by the scene that owns the deck
+#[derive(Debug, Clone, Copy)]
+pub enum ConsumableEffect {
+    Enhance(Enhancement),
+    ConvertSuit(Suit),
+    RankUp,
+    Destroy,
+    /// Left selected card becomes a copy of the right one
+    Copy,
+    CreateTarots(u8),
+    CreatePlanets(u8),
+    /// Of the given rarity, rolled by weight if `None`
+    CreateJoker(Option<Rarity>),
+    CopyLastUsed,
+    DoubleMoney { max: u32 },
+    JokerSellValue { max: u32 },
+    JokerEdition { odds: u32 },
+    LevelUp(PokerHand),
+    LevelUpAll,
+    Seal(Seal),
+    /// Foil, Holographic or Polychrome on the selected card
+    CardEdition,
+    DestroyRandom(u8),
+    /// Random enhanced cards of one of the given ranks
+    AddToHand { ranks: &'static [Rank], n: u8 },
+    Duplicate(u8),
+    /// Every card in hand takes the suit of a random one of them
+    UnifySuit,
+    /// Every card in hand takes the rank of a random one of them
+    UnifyRank,
+    HandSize(i8),
+    Money(u32),
+    SetMoney(u32),
+    NegativeJoker,
+    /// Destroys every other joker
+    PolychromeJoker,
+    /// Destroys every other joker
+    CopyJoker,
+}
+
+const NUMERALS: [&str; 22] = [
+    "0", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X",
+    "XI", "XII", "XIII", "XIV", "XV", "XVI", "XVII", "XVIII", "XIX", "XX", "XXI",
+];
+
+/// Draws a roman numeral out of strokes, centered horizontally on `center`
+fn numeral_into_canvas(canvas: &mut Canvas, n: usize, center: [i32; 2], scale: i32) {
+    let glyph_width = |c: char| match c {
+        'I' => scale,
+        _ => scale * 4,
+    };
+    let numeral = NUMERALS[n];
+    let width = numeral.chars().map(glyph_width).sum::<i32>() + (numeral.len() as i32 - 1) * scale;
+    let height = scale * 6;
+
+    let mut x = center[0] - width / 2;
+    let top = center[1] - height / 2;
+    let bottom = top + height - 1;
+    for c in numeral.chars() {
+        let right = x + glyph_width(c) - 1;
+        match c {
+            'I' => canvas.fill([x, top], [scale, height]),
+            'V' => {
+                canvas.line([x, top], [(x + right) / 2, bottom]);
+                canvas.line([right, top], [(x + right) / 2, bottom]);
+            }
+            'X' => {
+                canvas.line([x, top], [right, bottom]);
+                canvas.line([right, top], [x, bottom]);
+            }
+            _ => canvas.circle([(x + right) / 2, (top + bottom) / 2], scale * 2),
+        }
+        x = right + 1 + scale;
+    }
+}
@@ -0,0 +1,92 @@
+use rand::Rng;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+use wasm4::draw::DrawIndex;
+
+use crate::card::poker::PokerHand;
+use crate::gfx::texture::{Canvas, Texture, TextureColors};
+
+use super::ConsumableEffect;
+
+/// One planet per poker hand, in the same order
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Planet {
+    Pluto,
+    Mercury,
+    Uranus,
+    Venus,
+    Saturn,
+    Jupiter,
+    Earth,
+    Mars,
+    Neptune,
+    PlanetX,
+    Ceres,
+    Eris,
+}
+
+impl Planet {
+    pub fn random<T: Rng>(rng: &mut T) -> Self {
+        Planet::iter().nth(rng.gen_range(0..Planet::COUNT)).unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Planet::Pluto => "Pluto",
+            Planet::Mercury => "Mercury",
+            Planet::Uranus => "Uranus",
+            Planet::Venus => "Venus",
+            Planet::Saturn => "Saturn",
+            Planet::Jupiter => "Jupiter",
+            Planet::Earth => "Earth",
+            Planet::Mars => "Mars",
+            Planet::Neptune => "Neptune",
+            Planet::PlanetX => "Planet X",
+            Planet::Ceres => "Ceres",
+            Planet::Eris => "Eris",
+        }
+    }
+
+    pub fn from_hand(hand: PokerHand) -> Self {
+        Planet::iter().find(|planet| planet.hand() == hand).unwrap()
+    }
+
+    pub fn hand(&self) -> PokerHand {
+        PokerHand::iter().nth(*self as usize).unwrap()
+    }
+
+    pub fn effect(&self) -> ConsumableEffect {
+        ConsumableEffect::LevelUp(self.hand())
+    }
+
+    pub fn texture(&self) -> [Texture; 2] {
+        let mut canvas = Canvas::new();
+        let i = *self as i32;
+
+        canvas.rect([0, 0], [60, 80]);
+
+        // Stars in the background, scattered by planet so no two match
+        for star in 0..8 {
+            let x = (star * 17 + i * 7) % 52 + 4;
+            let y = (star * 29 + i * 11) % 72 + 4;
+            canvas.set(x, y);
+        }
+
+        let radius = 6 + (i * 5) % 10;
+        canvas.circle([30, 40], radius);
+        canvas.circle([30, 40], radius / 2);
+
+        match self {
+            // Ringed ones
+            Planet::Saturn | Planet::Uranus | Planet::Neptune => {
+                canvas.line([30 - radius - 6, 44], [30 + radius + 6, 36]);
+            }
+            _ => {
+                for moon in 0..i % 3 {
+                    canvas.circle([30 + radius + 4, 40 - radius + moon * 6], 1);
+                }
+            }
+        }
+
+        canvas.finish(TextureColors::OneBpp([DrawIndex::First, DrawIndex::Fourth]))
+    }
+}
@@ -0,0 +1,154 @@
+use core::ops::RangeInclusive;
+
+use heapless::Vec;
+use rand::Rng;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+use wasm4::draw::DrawIndex;
+
+use crate::card::{joker::Rarity, Rank, Seal};
+use crate::gfx::texture::{Canvas, Texture, TextureColors};
+
+use super::ConsumableEffect;
+
+const FACES: &[Rank] = &[Rank::Jack, Rank::Queen, Rank::King];
+const ACES: &[Rank] = &[Rank::Ace];
+const NUMBERED: &[Rank] = &[
+    Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
+    Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Spectral {
+    Familiar,
+    Grim,
+    Incantation,
+    Talisman,
+    Aura,
+    Wraith,
+    Sigil,
+    Ouija,
+    Ectoplasm,
+    Immolate,
+    Ankh,
+    DejaVu,
+    Hex,
+    Trance,
+    Medium,
+    Cryptid,
+    Soul,
+    BlackHole,
+}
+
+impl Spectral {
+    pub fn random<T: Rng>(rng: &mut T) -> Self {
+        Spectral::iter().nth(rng.gen_range(0..Spectral::COUNT)).unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Spectral::Familiar => "Familiar",
+            Spectral::Grim => "Grim",
+            Spectral::Incantation => "Incantation",
+            Spectral::Talisman => "Talisman",
+            Spectral::Aura => "Aura",
+            Spectral::Wraith => "Wraith",
+            Spectral::Sigil => "Sigil",
+            Spectral::Ouija => "Ouija",
+            Spectral::Ectoplasm => "Ectoplasm",
+            Spectral::Immolate => "Immolate",
+            Spectral::Ankh => "Ankh",
+            Spectral::DejaVu => "Deja Vu",
+            Spectral::Hex => "Hex",
+            Spectral::Trance => "Trance",
+            Spectral::Medium => "Medium",
+            Spectral::Cryptid => "Cryptid",
+            Spectral::Soul => "The Soul",
+            Spectral::BlackHole => "Black Hole",
+        }
+    }
+
+    /// How many hand cards have to be selected for the spectral to be used
+    pub fn selection(&self) -> RangeInclusive<usize> {
+        match self {
+            Spectral::Talisman
+            | Spectral::Aura
+            | Spectral::DejaVu
+            | Spectral::Trance
+            | Spectral::Medium
+            | Spectral::Cryptid => 1..=1,
+            _ => 0..=5,
+        }
+    }
+
+    /// Spectrals are a gamble, most of them come with a downside applied
+    /// right after the upside
+    pub fn effects(&self) -> Vec<ConsumableEffect, 2> {
+        let effects: &[ConsumableEffect] = match self {
+            Spectral::Familiar => &[
+                ConsumableEffect::DestroyRandom(1),
+                ConsumableEffect::AddToHand { ranks: FACES, n: 3 },
+            ],
+            Spectral::Grim => &[
+                ConsumableEffect::DestroyRandom(1),
+                ConsumableEffect::AddToHand { ranks: ACES, n: 2 },
+            ],
+            Spectral::Incantation => &[
+                ConsumableEffect::DestroyRandom(1),
+                ConsumableEffect::AddToHand { ranks: NUMBERED, n: 4 },
+            ],
+            Spectral::Talisman => &[ConsumableEffect::Seal(Seal::Gold)],
+            Spectral::Aura => &[ConsumableEffect::CardEdition],
+            Spectral::Wraith => &[
+                ConsumableEffect::CreateJoker(Some(Rarity::Rare)),
+                ConsumableEffect::SetMoney(0),
+            ],
+            Spectral::Sigil => &[ConsumableEffect::UnifySuit],
+            Spectral::Ouija => &[
+                ConsumableEffect::UnifyRank,
+                ConsumableEffect::HandSize(-1),
+            ],
+            Spectral::Ectoplasm => &[
+                ConsumableEffect::NegativeJoker,
+                ConsumableEffect::HandSize(-1),
+            ],
+            Spectral::Immolate => &[
+                ConsumableEffect::DestroyRandom(5),
+                ConsumableEffect::Money(20),
+            ],
+            Spectral::Ankh => &[ConsumableEffect::CopyJoker],
+            Spectral::DejaVu => &[ConsumableEffect::Seal(Seal::Red)],
+            Spectral::Hex => &[ConsumableEffect::PolychromeJoker],
+            Spectral::Trance => &[ConsumableEffect::Seal(Seal::Blue)],
+            Spectral::Medium => &[ConsumableEffect::Seal(Seal::Purple)],
+            Spectral::Cryptid => &[ConsumableEffect::Duplicate(2)],
+            Spectral::Soul => &[ConsumableEffect::CreateJoker(Some(Rarity::Legendary))],
+            Spectral::BlackHole => &[ConsumableEffect::LevelUpAll],
+        };
+        Vec::from_slice(effects).unwrap()
+    }
+
+    pub fn texture(&self) -> [Texture; 2] {
+        let mut canvas = Canvas::new();
+        let i = *self as i32;
+
+        canvas.rect([0, 0], [60, 80]);
+        canvas.rect([2, 2], [56, 76]);
+
+        // Crystal in the middle
+        canvas.line([30, 12], [46, 36]);
+        canvas.line([46, 36], [30, 60]);
+        canvas.line([30, 60], [14, 36]);
+        canvas.line([14, 36], [30, 12]);
+        canvas.line([14, 36], [46, 36]);
+        canvas.line([30, 12], [30, 60]);
+
+        // One tally mark per spectral, two rows of nine
+        for mark in 0..=i {
+            let x = 12 + (mark % 9) * 4;
+            let y = 65 + (mark / 9) * 6;
+            canvas.line([x, y], [x, y + 3]);
+        }
+
+        canvas.finish(TextureColors::OneBpp([DrawIndex::Second, DrawIndex::Fourth]))
+    }
+}
@@ -0,0 +1,157 @@
+use core::ops::RangeInclusive;
+
+use rand::Rng;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+use wasm4::draw::DrawIndex;
+
+use crate::card::{suit_into_buffer, Enhancement, Suit};
+use crate::gfx::texture::{Canvas, Texture, TextureColors, TEXTURE_WIDTH};
+
+use super::{numeral_into_canvas, ConsumableEffect};
+
+/// The 22 Major Arcana, in numeral order
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum Tarot {
+    Fool,
+    Magician,
+    HighPriestess,
+    Empress,
+    Emperor,
+    Hierophant,
+    Lovers,
+    Chariot,
+    Justice,
+    Hermit,
+    WheelOfFortune,
+    Strength,
+    HangedMan,
+    Death,
+    Temperance,
+    Devil,
+    Tower,
+    Star,
+    Moon,
+    Sun,
+    Judgement,
+    World,
+}
+
+impl Tarot {
+    pub fn random<T: Rng>(rng: &mut T) -> Self {
+        Tarot::iter().nth(rng.gen_range(0..Tarot::COUNT)).unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tarot::Fool => "The Fool",
+            Tarot::Magician => "The Magician",
+            Tarot::HighPriestess => "The High Priestess",
+            Tarot::Empress => "The Empress",
+            Tarot::Emperor => "The Emperor",
+            Tarot::Hierophant => "The Hierophant",
+            Tarot::Lovers => "The Lovers",
+            Tarot::Chariot => "The Chariot",
+            Tarot::Justice => "Justice",
+            Tarot::Hermit => "The Hermit",
+            Tarot::WheelOfFortune => "Wheel of Fortune",
+            Tarot::Strength => "Strength",
+            Tarot::HangedMan => "The Hanged Man",
+            Tarot::Death => "Death",
+            Tarot::Temperance => "Temperance",
+            Tarot::Devil => "The Devil",
+            Tarot::Tower => "The Tower",
+            Tarot::Star => "The Star",
+            Tarot::Moon => "The Moon",
+            Tarot::Sun => "The Sun",
+            Tarot::Judgement => "Judgement",
+            Tarot::World => "The World",
+        }
+    }
+
+    /// How many hand cards have to be selected for the tarot to be used
+    pub fn selection(&self) -> RangeInclusive<usize> {
+        match self {
+            Tarot::Magician
+            | Tarot::Empress
+            | Tarot::Hierophant
+            | Tarot::Strength
+            | Tarot::HangedMan => 1..=2,
+            Tarot::Lovers
+            | Tarot::Chariot
+            | Tarot::Justice
+            | Tarot::Devil
+            | Tarot::Tower => 1..=1,
+            Tarot::Death => 2..=2,
+            Tarot::Star
+            | Tarot::Moon
+            | Tarot::Sun
+            | Tarot::World => 1..=3,
+            _ => 0..=5,
+        }
+    }
+
+    pub fn effect(&self) -> ConsumableEffect {
+        match self {
+            Tarot::Fool => ConsumableEffect::CopyLastUsed,
+            Tarot::Magician => ConsumableEffect::Enhance(Enhancement::Lucky),
+            Tarot::HighPriestess => ConsumableEffect::CreatePlanets(2),
+            Tarot::Empress => ConsumableEffect::Enhance(Enhancement::Mult),
+            Tarot::Emperor => ConsumableEffect::CreateTarots(2),
+            Tarot::Hierophant => ConsumableEffect::Enhance(Enhancement::Bonus),
+            Tarot::Lovers => ConsumableEffect::Enhance(Enhancement::Wild),
+            Tarot::Chariot => ConsumableEffect::Enhance(Enhancement::Steel),
+            Tarot::Justice => ConsumableEffect::Enhance(Enhancement::Glass),
+            Tarot::Hermit => ConsumableEffect::DoubleMoney { max: 20 },
+            Tarot::WheelOfFortune => ConsumableEffect::JokerEdition { odds: 4 },
+            Tarot::Strength => ConsumableEffect::RankUp,
+            Tarot::HangedMan => ConsumableEffect::Destroy,
+            Tarot::Death => ConsumableEffect::Copy,
+            Tarot::Temperance => ConsumableEffect::JokerSellValue { max: 50 },
+            Tarot::Devil => ConsumableEffect::Enhance(Enhancement::Gold),
+            Tarot::Tower => ConsumableEffect::Enhance(Enhancement::Stone),
+            Tarot::Star => ConsumableEffect::ConvertSuit(Suit::Diamond),
+            Tarot::Moon => ConsumableEffect::ConvertSuit(Suit::Club),
+            Tarot::Sun => ConsumableEffect::ConvertSuit(Suit::Heart),
+            Tarot::Judgement => ConsumableEffect::CreateJoker(None),
+            Tarot::World => ConsumableEffect::ConvertSuit(Suit::Spade),
+        }
+    }
+
+    pub fn texture(&self) -> [Texture; 2] {
+        let mut canvas = Canvas::new();
+
+        // Double frame
+        canvas.rect([0, 0], [60, 80]);
+        canvas.rect([3, 3], [54, 74]);
+
+        numeral_into_canvas(&mut canvas, *self as usize, [30, 18], 2);
+
+        match self.effect() {
+            ConsumableEffect::ConvertSuit(suit) => {
+                suit_into_buffer(canvas.bits(), suit, 25 + TEXTURE_WIDTH * 44);
+            }
+            _ => {
+                // Sun-like emblem, rays count follows the numeral
+                canvas.circle([30, 50], 8);
+                for ray in 0..=*self as i32 {
+                    let angle = ray as f32 * core::f32::consts::TAU / (*self as i32 + 1) as f32;
+                    let (sin, cos) = (sin(angle), sin(angle + core::f32::consts::FRAC_PI_2));
+                    canvas.line(
+                        [30 + (sin * 10.0) as i32, 50 + (cos * 10.0) as i32],
+                        [30 + (sin * 16.0) as i32, 50 + (cos * 16.0) as i32],
+                    );
+                }
+            }
+        }
+
+        canvas.finish(TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::Second]))
+    }
+}
+
+// Bhaskara's approximation, good enough for placing a few pixels
+fn sin(x: f32) -> f32 {
+    use core::f32::consts::PI;
+    let x = x % (2.0 * PI);
+    let (x, sign) = if x > PI { (x - PI, -1.0) } else { (x, 1.0) };
+    sign * 16.0 * x * (PI - x) / (5.0 * PI * PI - 4.0 * x * (PI - x))
+}
@@ -1,42 +1,324 @@
-use core::ops::Deref;
+use core::ops::{Deref, Range};
 
 use super::card::{Card, Rank, Suit};
+use bitvec::{order::Msb0, view::AsMutBits};
 use heapless::Vec;
 use rand::{seq::SliceRandom, Rng};
-use strum::IntoEnumIterator;
+use strum::{EnumIter, IntoEnumIterator};
+use wasm4::draw::DrawIndex;
 
-// pub enum DeckType {
-//     Default,
-//     // TODO: potentially add deck effects maybe
-// }
+use crate::gfx::texture::{Texture, TextureColors, CARD_UV0, CARD_UV1, TEXTURE_BUFFER, TEXTURE_HEIGHT, TEXTURE_WIDTH};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum DeckType {
+    #[default]
+    Red,
+    Blue,
+    Yellow,
+    Abandoned,
+    Checkered,
+    Erratic,
+    Plasma,
+}
+
+impl DeckType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeckType::Red => "Red",
+            DeckType::Blue => "Blue",
+            DeckType::Yellow => "Yellow",
+            DeckType::Abandoned => "Abandoned",
+            DeckType::Checkered => "Checkered",
+            DeckType::Erratic => "Erratic",
+            DeckType::Plasma => "Plasma",
+        }
+    }
+    pub fn next(&self) -> Self {
+        Self::iter()
+            .cycle()
+            .skip_while(|kind| kind != self)
+            .nth(1)
+            .unwrap()
+    }
+
+    pub fn extra_discards(&self) -> u8 {
+        match self {
+            DeckType::Red => 1,
+            _ => 0,
+        }
+    }
+    pub fn extra_hands(&self) -> u8 {
+        match self {
+            DeckType::Blue => 1,
+            _ => 0,
+        }
+    }
+    pub fn starting_money(&self) -> u32 {
+        match self {
+            DeckType::Yellow => 14,
+            _ => 4,
+        }
+    }
+    /// Plasma deck averages chips and mult before the final multiplication
+    pub fn balances_score(&self) -> bool {
+        *self == DeckType::Plasma
+    }
+
+    fn compose<T: Rng>(&self, rng: &mut T) -> Vec<Card, 256> {
+        let mut cards: Vec<Card, 256> = Vec::new();
+        match self {
+            DeckType::Abandoned => {
+                for suit in Suit::iter() {
+                    for rank in Rank::iter().filter(|rank| !matches!(rank, Rank::Jack | Rank::Queen | Rank::King)) {
+                        cards.push(Card::new(suit, rank)).unwrap();
+                    }
+                }
+            }
+            DeckType::Checkered => {
+                for suit in [Suit::Spade, Suit::Spade, Suit::Heart, Suit::Heart] {
+                    for rank in Rank::iter() {
+                        cards.push(Card::new(suit, rank)).unwrap();
+                    }
+                }
+            }
+            DeckType::Erratic => {
+                for _ in 0..52 {
+                    cards.push(Card::random(rng)).unwrap();
+                }
+            }
+            _ => {
+                for suit in Suit::iter() {
+                    for rank in Rank::iter() {
+                        cards.push(Card::new(suit, rank)).unwrap();
+                    }
+                }
+            }
+        }
+        cards
+    }
+
+    pub fn texture(&self) -> [Texture; 2] {
+        let buf = unsafe { TEXTURE_BUFFER.as_mut() }.unwrap();
+        let bitbuf = buf.as_mut_bits::<Msb0>();
+
+        let pattern: fn(usize, usize) -> bool = match self {
+            DeckType::Red | DeckType::Blue => |x, y| (x + y) % 8 < 3,
+            DeckType::Yellow => |x, y| x % 6 < 2 && y % 6 < 2,
+            DeckType::Abandoned => |x, y| (x * 7 + y * 13) % 17 == 0,
+            DeckType::Checkered => |x, y| (x / 6 + y / 6) % 2 == 0,
+            DeckType::Erratic => |x, y| (x * x + y * 3 + x * y) % 5 == 0,
+            DeckType::Plasma => |x, y| (x.abs_diff(30) + y.abs_diff(40)) % 8 < 3,
+        };
+
+        for y in 0..TEXTURE_HEIGHT {
+            for x in 0..TEXTURE_WIDTH {
+                let border = x < 3 || y < 3 || x >= TEXTURE_WIDTH - 3 || y >= TEXTURE_HEIGHT - 3;
+                bitbuf.set(x + y * TEXTURE_WIDTH, border || pattern(x, y));
+            }
+        }
+
+        let colors = match self {
+            DeckType::Red => TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::Third]),
+            DeckType::Blue | DeckType::Abandoned => TextureColors::OneBpp([DrawIndex::Fourth, DrawIndex::First]),
+            DeckType::Yellow | DeckType::Erratic => TextureColors::OneBpp([DrawIndex::Third, DrawIndex::Second]),
+            DeckType::Checkered => TextureColors::OneBpp([DrawIndex::Third, DrawIndex::Fourth]),
+            DeckType::Plasma => TextureColors::OneBpp([DrawIndex::First, DrawIndex::Second]),
+        };
+
+        [ Texture { buf, uv: CARD_UV0, colors },
+          Texture { buf, uv: CARD_UV1, colors } ]
+    }
+}
+
+/// Most cards the hand can hold, whatever its size
+pub const HAND_CAPACITY: usize = 10;
+
+/// Deck type, card count and three bytes per card
+pub const DECK_SAVE_SIZE: usize = 3 + 256 * 3;
 
 pub struct Deck {
+    // Every card the player owns, wherever it currently is
     cards: Vec<Card, 256>,
-    // kind: DeckType,
+    // Ids of the draw, discard and played piles back to back, the top of
+    // each pile last. Cards in hand are in none of them.
+    piles: Vec<u8, 256>,
+    // Where the discard and played piles start in `piles`
+    discard_start: usize,
+    played_start: usize,
+    kind: DeckType,
 }
 
 impl Deck {
-    pub fn new() -> Self {
-        let mut cards: Vec<Card, 256> = Vec::new();
+    pub fn new<T: Rng>(kind: DeckType, rng: &mut T) -> Self {
+        let mut cards = kind.compose(rng);
+        cards
+            .iter_mut()
+            .enumerate()
+            .for_each(|(id, card)| card.set_id(id as u8));
+
+        let mut deck = Self {
+            cards,
+            piles: Vec::new(),
+            discard_start: 0,
+            played_start: 0,
+            kind,
+        };
+        deck.reset(rng);
+        deck
+    }
+
+    pub fn kind(&self) -> DeckType {
+        self.kind
+    }
 
-        for suit in Suit::iter() {
-            for rank in Rank::iter() {
-                cards.push(Card::new(suit, rank)).unwrap();
+    /// Only the cards are saved, piles are rebuilt by [`Deck::reset`]
+    pub fn save(&self) -> Vec<u8, DECK_SAVE_SIZE> {
+        let mut bytes = Vec::new();
+        bytes.push(self.kind as u8).unwrap();
+        bytes.extend_from_slice(&(self.cards.len() as u16).to_le_bytes()).unwrap();
+        self.cards
+            .iter()
+            .for_each(|card| bytes.extend_from_slice(&card.to_bytes()).unwrap());
+        bytes
+    }
+
+    /// Rebuilds a saved deck with everything in the draw pile, `None` if the
+    /// save is corrupted
+    pub fn load<T: Rng>(bytes: &[u8], rng: &mut T) -> Option<Self> {
+        let (header, body) = bytes.split_at_checked(3)?;
+        let kind = DeckType::iter().nth(header[0] as usize)?;
+        let len = u16::from_le_bytes([header[1], header[2]]) as usize;
+
+        let mut cards: Vec<Card, 256> = Vec::new();
+        for chunk in body.get(..len * 3)?.chunks_exact(3) {
+            let card = Card::from_bytes([chunk[0], chunk[1], chunk[2]])?;
+            // Ids are handles, two cards can't share one
+            if cards.iter().any(|other| other.id() == card.id()) {
+                return None;
             }
+            cards.push(card).ok()?;
         }
 
-        Self {
+        let mut deck = Self {
             cards,
-            // kind: DeckType::Default,
-        }
+            piles: Vec::new(),
+            discard_start: 0,
+            played_start: 0,
+            kind,
+        };
+        deck.reset(rng);
+        Some(deck)
     }
 
     pub fn draw(&mut self) -> Option<Card> {
-        self.cards.pop()
+        let top = self.discard_start.checked_sub(1)?;
+        let id = self.piles.remove(top);
+        self.discard_start -= 1;
+        self.played_start -= 1;
+        self.card(id).copied()
+    }
+
+    pub fn discard(&mut self, card: &Card) {
+        self.piles.insert(self.played_start, card.id()).unwrap();
+        self.played_start += 1;
+    }
+
+    pub fn play(&mut self, card: &Card) {
+        self.piles.push(card.id()).unwrap();
     }
 
     pub fn shuffle<T: Rng>(&mut self, rng: &mut T) {
-        self.cards.shuffle(rng);
+        self.piles[..self.discard_start].shuffle(rng);
+    }
+
+    /// Gathers every card back into the draw pile and shuffles it.
+    /// Cards still held in hand are expected to be dropped by the caller.
+    pub fn reset<T: Rng>(&mut self, rng: &mut T) {
+        self.piles = self.cards.iter().map(Card::id).collect();
+        self.discard_start = self.piles.len();
+        self.played_start = self.piles.len();
+        self.shuffle(rng);
+    }
+
+    pub fn card(&self, id: u8) -> Option<&Card> {
+        self.cards.iter().find(|card| card.id() == id)
+    }
+
+    /// Adds a new card to the bottom of the draw pile
+    pub fn add(&mut self, card: Card) -> Option<Card> {
+        let card = self.register(card)?;
+        self.piles.insert(0, card.id()).ok()?;
+        self.discard_start += 1;
+        self.played_start += 1;
+        Some(card)
+    }
+
+    /// Adds a new card straight into the hand, it is picked up by
+    /// `HandState::sync`. A full hand sends it to the draw pile instead.
+    pub fn add_to_hand(&mut self, card: Card) -> Option<Card> {
+        if self.hand().count() >= HAND_CAPACITY {
+            return self.add(card);
+        }
+        self.register(card)
+    }
+
+    /// Permanently removes a card from the deck, wherever it is
+    pub fn destroy(&mut self, id: u8) -> Option<Card> {
+        let pos = self.cards.iter().position(|card| card.id() == id)?;
+        if let Some(at) = self.piles.iter().position(|other| *other == id) {
+            self.piles.remove(at);
+            if at < self.discard_start {
+                self.discard_start -= 1;
+            }
+            if at < self.played_start {
+                self.played_start -= 1;
+            }
+        }
+        Some(self.cards.swap_remove(pos))
+    }
+
+    /// Copies a card, the copy ends up next to the original: in hand for
+    /// cards held in hand, otherwise in the draw pile
+    pub fn duplicate(&mut self, id: u8) -> Option<Card> {
+        let card = *self.card(id)?;
+        if self.in_hand(id) {
+            self.add_to_hand(card)
+        } else {
+            self.add(card)
+        }
+    }
+
+    pub fn transform(&mut self, id: u8, f: impl FnOnce(&mut Card)) -> Option<&Card> {
+        let card = self.cards.iter_mut().find(|card| card.id() == id)?;
+        f(card);
+        Some(card)
+    }
+
+    /// Cards that are in none of the piles are held in hand
+    pub fn in_hand(&self, id: u8) -> bool {
+        self.card(id).is_some() && !self.piles.contains(&id)
+    }
+    pub fn hand(&self) -> impl Iterator<Item = &Card> {
+        self.cards.iter().filter(|card| self.in_hand(card.id()))
+    }
+
+    fn register(&mut self, mut card: Card) -> Option<Card> {
+        let id = (0..=u8::MAX).find(|id| self.card(*id).is_none())?;
+        card.set_id(id);
+        self.cards.push(card).ok()?;
+        Some(card)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.discard_start
+    }
+
+    pub fn draw_pile(&self) -> impl Iterator<Item = &Card> {
+        self.pile(0..self.discard_start)
+    }
+
+    fn pile(&self, range: Range<usize>) -> impl Iterator<Item = &Card> {
+        self.piles[range].iter().filter_map(|id| self.card(*id))
     }
 }
 
@@ -47,3 +329,90 @@ impl Deref for Deck {
         self.cards.as_slice()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+
+    fn deck() -> (Deck, SmallRng) {
+        let mut rng = SmallRng::seed_from_u64(1);
+        (Deck::new(DeckType::Red, &mut rng), rng)
+    }
+
+    #[test]
+    fn cards_move_between_piles() {
+        let (mut deck, mut rng) = deck();
+        assert_eq!(deck.remaining(), 52);
+
+        let drawn: Vec<Card, 3> = (0..3).map(|_| deck.draw().unwrap()).collect();
+        assert_eq!(deck.remaining(), 49);
+        assert_eq!(deck.hand().count(), 3);
+
+        deck.discard(&drawn[0]);
+        deck.play(&drawn[1]);
+        assert_eq!(deck.pile(deck.discard_start..deck.played_start).next(), Some(&drawn[0]));
+        assert_eq!(deck.pile(deck.played_start..deck.piles.len()).next(), Some(&drawn[1]));
+        assert!(deck.in_hand(drawn[2].id()));
+
+        // The discard pile sits between the other two
+        deck.destroy(drawn[0].id()).unwrap();
+        assert_eq!(deck.pile(deck.discard_start..deck.played_start).count(), 0);
+        assert_eq!(deck.pile(deck.played_start..deck.piles.len()).next(), Some(&drawn[1]));
+        assert_eq!(deck.remaining(), 49);
+
+        deck.reset(&mut rng);
+        assert_eq!(deck.remaining(), 51);
+        assert_eq!(deck.hand().count(), 0);
+    }
+
+    #[test]
+    fn added_cards_go_to_the_bottom() {
+        let (mut deck, _) = deck();
+        let added = deck.add(Card::new(Suit::Heart, Rank::Ace)).unwrap();
+        assert_eq!(deck.draw_pile().next(), Some(&added));
+        assert_eq!(deck.remaining(), 53);
+    }
+
+    #[test]
+    fn full_hand_sends_cards_to_the_draw_pile() {
+        let (mut deck, _) = deck();
+        (0..HAND_CAPACITY).for_each(|_| {
+            deck.draw();
+        });
+
+        let card = deck.add_to_hand(Card::new(Suit::Club, Rank::Two)).unwrap();
+        assert!(!deck.in_hand(card.id()));
+        assert_eq!(deck.hand().count(), HAND_CAPACITY);
+    }
+
+    #[test]
+    fn save_round_trip() {
+        let (mut deck, mut rng) = deck();
+        deck.destroy(5);
+        deck.transform(9, |card| card.enhance(crate::card::Enhancement::Glass));
+
+        let loaded = Deck::load(&deck.save(), &mut rng).unwrap();
+        assert_eq!(loaded.kind(), DeckType::Red);
+        assert_eq!(&*loaded, &*deck);
+        assert_eq!(loaded.remaining(), 51);
+    }
+
+    #[test]
+    fn load_rejects_corrupted_saves() {
+        let (deck, mut rng) = deck();
+        let bytes = deck.save();
+
+        assert!(Deck::load(&bytes[..bytes.len() - 1], &mut rng).is_none());
+
+        let mut bad_kind = bytes.clone();
+        bad_kind[0] = 0xFF;
+        assert!(Deck::load(&bad_kind, &mut rng).is_none());
+
+        // Second card takes the first one's id
+        let mut shared_id = bytes.clone();
+        shared_id[6] = shared_id[3];
+        assert!(Deck::load(&shared_id, &mut rng).is_none());
+    }
+}
@@ -1,13 +1,119 @@
+use enumflags2::BitFlags;
 use smart_default::SmartDefault;
 
-#[derive(Debug, SmartDefault)]
+use crate::card::{consumable::Consumable, poker::PokerHand, Card};
+
+use super::{Joker, JokerType};
+
+#[derive(Debug, Clone, Copy, SmartDefault)]
 pub struct NumberEffect {
-    points: u32,
-    mult: u32,
-    #[default = 1]
-    multx: u32,
+    pub points: u32,
+    pub mult: u32,
+    #[default = 1.0]
+    pub multx: f32,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum Effect {
     Numbers(NumberEffect),
+    Money(u32),
+    /// The joker destroys itself
+    Destroy,
+}
+
+impl Effect {
+    pub fn chips(points: u32) -> Self {
+        Effect::Numbers(NumberEffect { points, ..Default::default() })
+    }
+    pub fn mult(mult: u32) -> Self {
+        Effect::Numbers(NumberEffect { mult, ..Default::default() })
+    }
+    pub fn multx(multx: f32) -> Self {
+        Effect::Numbers(NumberEffect { multx, ..Default::default() })
+    }
+}
+
+/// Moments outside of scoring a joker can react to
+#[repr(u16)]
+#[enumflags2::bitflags]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Trigger {
+    /// Before the played hand is scored
+    HandPlayed,
+    HandScored,
+    Discard,
+    BlindSelected,
+    RoundEnd,
+    Reroll,
+    CardAdded,
+    JokerSold,
+    ConsumableUsed,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum JokerEvent<'a> {
+    HandPlayed {
+        contained: BitFlags<PokerHand>,
+        played: &'a [Card],
+    },
+    HandScored,
+    Discard(&'a [Card]),
+    BlindSelected,
+    RoundEnd,
+    /// Fired by the shop
+    Reroll,
+    CardAdded(Card),
+    JokerSold(JokerType),
+    ConsumableUsed(Consumable),
+}
+
+impl<'a> JokerEvent<'a> {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            JokerEvent::HandPlayed { .. } => Trigger::HandPlayed,
+            JokerEvent::HandScored => Trigger::HandScored,
+            JokerEvent::Discard(_) => Trigger::Discard,
+            JokerEvent::BlindSelected => Trigger::BlindSelected,
+            JokerEvent::RoundEnd => Trigger::RoundEnd,
+            JokerEvent::Reroll => Trigger::Reroll,
+            JokerEvent::CardAdded(_) => Trigger::CardAdded,
+            JokerEvent::JokerSold(_) => Trigger::JokerSold,
+            JokerEvent::ConsumableUsed(_) => Trigger::ConsumableUsed,
+        }
+    }
+    /// Cards of the played hand, empty for every other event
+    pub fn played(&self) -> &'a [Card] {
+        match self {
+            JokerEvent::HandPlayed { played, .. } => played,
+            _ => &[],
+        }
+    }
+    /// Poker hands the played hand contains, empty for every other event
+    pub fn contained(&self) -> BitFlags<PokerHand> {
+        match self {
+            JokerEvent::HandPlayed { contained, .. } => *contained,
+            _ => BitFlags::empty(),
+        }
+    }
+}
+
+pub type EventProc = fn(&mut Joker, &JokerEvent, &GameView) -> Option<Effect>;
+
+/// Read-only snapshot of the game a joker gets to look at when it procs
+#[derive(Debug)]
+pub struct GameView<'a> {
+    /// Best poker hand of the played cards, outside of scoring the last
+    /// one played and `played` is empty
+    pub hand: PokerHand,
+    /// Every poker hand the played cards contain, for "contains a Pair" jokers
+    pub contained: BitFlags<PokerHand>,
+    pub played: &'a [Card],
+    pub held: &'a [Card],
+    /// Cards left in the draw pile
+    pub deck_size: usize,
+    pub money: u32,
+    /// Discards left this round
+    pub discards: u8,
+    /// Hands left this round, 0 while scoring the final one
+    pub hands: u8,
 }
@@ -1,55 +1,391 @@
-use joker_effects::Effect;
-use textures::JIMBO;
-use wasm4::draw::DrawIndex;
+use enumflags2::BitFlags;
+use rand::{seq::IteratorRandom, Rng};
+pub use joker_effects::{Effect, EventProc, GameView, JokerEvent, NumberEffect, Trigger};
+use strum::{EnumIter, IntoEnumIterator};
+use wasm4::format::format_no_std;
 
-use crate::gfx::texture::{Texture, TextureColors, CARD_UV0, CARD_UV1};
+use crate::gfx::texture::{Texture, CARD_UV0, CARD_UV1};
 
-use super::Card;
+use super::{consumable::Consumable, poker::PokerHand, Card, Edition, Rank, Suit};
 
 mod textures;
 mod joker_effects;
 
-#[derive(Debug)]
-pub enum JokerType {
-    Jimbo,
+include!(concat!(env!("OUT_DIR"), "/jokers.rs"));
+
+/// Put on jokers by higher stakes
+#[repr(u8)]
+#[enumflags2::bitflags]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Sticker {
+    /// Can't be sold or destroyed
+    Eternal,
+    /// Debuffed after a few rounds
+    Perishable,
+    /// Cheap to buy but costs money every round
+    Rental,
 }
 
-#[derive(Debug)]
+/// Rounds a perishable joker works for
+const PERISHABLE_ROUNDS: u8 = 5;
+const RENT: u32 = 3;
+
+impl Sticker {
+    /// Odds out of 100 of a shop joker getting the sticker
+    fn odds(&self) -> u32 {
+        match self {
+            Sticker::Eternal => 30,
+            Sticker::Perishable => 30,
+            Sticker::Rental => 30,
+        }
+    }
+    /// Lowest stake shop jokers come with the sticker on
+    fn stake(&self) -> Stake {
+        match self {
+            Sticker::Eternal => Stake::Black,
+            Sticker::Perishable => Stake::Orange,
+            Sticker::Rental => Stake::Gold,
+        }
+    }
+}
+
+/// Difficulty of a run, every stake adds to the ones below it. Only the
+/// stickers are in so far.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, EnumIter)]
+pub enum Stake {
+    #[default]
+    White,
+    Red,
+    Green,
+    Black,
+    Blue,
+    Purple,
+    Orange,
+    Gold,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    /// Only ever created by The Soul
+    Legendary,
+}
+
+impl Rarity {
+    /// Odds out of 100 of a generated joker being of this rarity
+    fn weight(&self) -> u32 {
+        match self {
+            Rarity::Common => 70,
+            Rarity::Uncommon => 25,
+            Rarity::Rare => 5,
+            Rarity::Legendary => 0,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Legendary => "Legendary",
+        }
+    }
+}
+
+impl JokerType {
+    /// The one place new jokers come from. Rarity is rolled by weight unless
+    /// `rarity` pins it, jokers in `owned` are skipped unless a Showman is
+    /// among them. Jimbo fills in when nothing is left to pick from.
+    pub fn generate<T: Rng>(rng: &mut T, rarity: Option<Rarity>, owned: &[JokerType]) -> Self {
+        let rarity = rarity.unwrap_or_else(|| {
+            let roll = rng.gen_range(0..100);
+            let mut odds = 0;
+            Rarity::iter()
+                .find(|rarity| {
+                    odds += rarity.weight();
+                    roll < odds
+                })
+                .unwrap_or(Rarity::Common)
+        });
+        let duplicates = owned.contains(&JokerType::Showman);
+        JokerType::iter()
+            .filter(|kind| kind.rarity() == rarity)
+            .filter(|kind| duplicates || !owned.contains(kind))
+            .choose(rng)
+            .unwrap_or(JokerType::Jimbo)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum ProcType {
     Passive,
     Blind,
-    Normal,
-    Card(fn (Card) -> Option<Effect>), //
+    /// Once per hand, after every scored card
+    Independent(fn(&Joker, &GameView) -> Option<Effect>),
+    /// Once for every scored card, right after the card itself
+    Card(fn(&Joker, &Card, &GameView) -> Option<Effect>),
+    /// How many extra times a scored card goes through its triggers, gets
+    /// the card's position among the scored cards
+    Retrigger(fn(&Joker, &Card, usize, &GameView) -> u8),
+    /// Uses the ability of another joker in the row
+    Copy(CopyTarget),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CopyTarget {
+    /// The joker right of this one
+    Right,
+    Leftmost,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Joker {
     kind: JokerType,
     proc: ProcType,
+    triggers: BitFlags<Trigger>,
+    on_event: EventProc,
+    edition: Option<Edition>,
+    // What scaling jokers have built up so far
+    counter: u16,
+    stickers: BitFlags<Sticker>,
+    // Until a perishable joker is debuffed
+    rounds_left: u8,
 }
 
 impl Joker {
     pub fn new(kind: JokerType) -> Self {
-        // let effect = match kind {
-        //     JokerType::Jimbo => Effect { mult: 4, ..Default::default() },
-        // };
-        // Self { kind, effect }
-        Self { kind, proc: ProcType::Normal }
+        let (triggers, on_event) = kind.events();
+        Self {
+            kind,
+            proc: kind.proc(),
+            triggers,
+            on_event,
+            edition: None,
+            counter: 0,
+            stickers: BitFlags::empty(),
+            rounds_left: 0,
+        }
+    }
+    /// Joker as stored on disk, see [`Joker::from_bytes`]
+    pub fn to_bytes(&self) -> [u8; 6] {
+        let edition = self.edition.map_or(0, |edition| edition as u8 + 1);
+        let [low, high] = self.counter.to_le_bytes();
+        [self.kind as u8, edition, low, high, self.stickers.bits(), self.rounds_left]
+    }
+    pub fn from_bytes(bytes: [u8; 6]) -> Option<Self> {
+        let mut joker = Self::new(JokerType::iter().nth(bytes[0] as usize)?);
+        if bytes[1] > 0 {
+            joker.edition = Some(Edition::iter().nth(bytes[1] as usize - 1)?);
+        }
+        joker.counter = u16::from_le_bytes([bytes[2], bytes[3]]);
+        if joker.counter > joker.kind.limit() {
+            return None;
+        }
+        joker.stickers = BitFlags::from_bits(bytes[4]).ok()?;
+        joker.rounds_left = bytes[5];
+        Some(joker)
+    }
+    /// Eternal and Perishable rule each other out, the later one is ignored
+    pub fn add_sticker(&mut self, sticker: Sticker) {
+        let excluded = match sticker {
+            Sticker::Eternal => Sticker::Perishable,
+            Sticker::Perishable => Sticker::Eternal,
+            Sticker::Rental => return self.stickers.insert(sticker),
+        };
+        if self.stickers.contains(excluded) {
+            return;
+        }
+        if sticker == Sticker::Perishable {
+            self.rounds_left = PERISHABLE_ROUNDS;
+        }
+        self.stickers.insert(sticker);
+    }
+    /// Rolls every sticker `stake` allows by its odds, Eternal is rolled
+    /// before Perishable
+    pub fn roll_stickers<T: Rng>(&mut self, rng: &mut T, stake: Stake) {
+        for sticker in BitFlags::<Sticker>::all() {
+            if stake >= sticker.stake() && rng.gen_range(0..100) < sticker.odds() {
+                self.add_sticker(sticker);
+            }
+        }
+    }
+    pub fn is_eternal(&self) -> bool {
+        self.stickers.contains(Sticker::Eternal)
+    }
+    /// A debuffed joker does nothing at all
+    pub fn is_debuffed(&self) -> bool {
+        self.stickers.contains(Sticker::Perishable) && self.rounds_left == 0
+    }
+    /// Counts down perishable jokers, returns the rent to pay
+    pub fn end_round(&mut self) -> u32 {
+        if self.stickers.contains(Sticker::Perishable) {
+            self.rounds_left = self.rounds_left.saturating_sub(1);
+        }
+        match self.stickers.contains(Sticker::Rental) {
+            true => RENT,
+            false => 0,
+        }
+    }
+    pub fn copies(&self) -> Option<CopyTarget> {
+        match self.proc {
+            ProcType::Copy(target) => Some(target),
+            _ => None,
+        }
+    }
+    pub fn reacts_to(&self, trigger: Trigger) -> bool {
+        self.triggers.contains(trigger)
+    }
+    /// Effect of an event outside of scoring, jokers may update their own
+    /// state here
+    pub fn on_event(&mut self, event: &JokerEvent, view: &GameView) -> Option<Effect> {
+        if !self.reacts_to(event.trigger()) || self.is_debuffed() {
+            return None;
+        }
+        (self.on_event)(self, event, view)
+    }
+    /// Effect on a single scored card
+    pub fn on_card(&self, card: &Card, view: &GameView) -> Option<Effect> {
+        if self.is_debuffed() {
+            return None;
+        }
+        match self.proc {
+            ProcType::Card(proc) => proc(self, card, view),
+            _ => None,
+        }
+    }
+    /// Extra triggers for the scored card at `pos`
+    pub fn retriggers(&self, card: &Card, pos: usize, view: &GameView) -> u8 {
+        if self.is_debuffed() {
+            return 0;
+        }
+        match self.proc {
+            ProcType::Retrigger(proc) => proc(self, card, pos, view),
+            _ => 0,
+        }
+    }
+    /// Effect once the scored cards are done
+    pub fn independent(&self, view: &GameView) -> Option<Effect> {
+        if self.is_debuffed() {
+            return None;
+        }
+        match self.proc {
+            ProcType::Independent(proc) => proc(self, view),
+            _ => None,
+        }
+    }
+    pub fn kind(&self) -> JokerType {
+        self.kind
+    }
+    pub fn edition(&self) -> Option<Edition> {
+        self.edition
+    }
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = Some(edition);
+    }
+    pub fn remove_edition(&mut self) {
+        self.edition = None;
+    }
+    /// Half the buy price rounded down, plus whatever the joker grew
+    pub fn sell_value(&self) -> u32 {
+        (self.cost() / 2).max(1) + self.growth()
+    }
+    /// Buy price, rentals go for $1
+    pub fn cost(&self) -> u32 {
+        match self.stickers.contains(Sticker::Rental) {
+            true => 1,
+            false => self.kind.cost(),
+        }
     }
     pub fn texture(&self) -> [Texture; 2] {
+        // Stickers and editions need drawing over, art is used as is otherwise
+        let plain = self.stickers.is_empty()
+            && !self.is_debuffed()
+            && matches!(self.edition, None | Some(Edition::Negative));
         // TODO: use zip for textures
-        let buf = &match self.kind {
-            JokerType::Jimbo => JIMBO,
+        let mut textures = match self.kind.art() {
+            Some(buf) if plain => {
+                let colors = textures::ART_COLORS;
+                [ Texture { buf, uv: CARD_UV0, colors },
+                  Texture { buf, uv: CARD_UV1, colors } ]
+            }
+            Some(art) => textures::over_art(self, art),
+            // The rest are drawn on the fly
+            None => textures::draw(self),
         };
-        let colors = match self.kind {
-            JokerType::Jimbo => TextureColors::TwoBpp([
-                DrawIndex::Second,
-                DrawIndex::First,
-                DrawIndex::Third,
-                DrawIndex::Fourth,
-            ]),
+        if self.edition == Some(Edition::Negative) {
+            textures.iter_mut().for_each(|texture| texture.colors = texture.colors.inverted());
+        }
+        textures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn counters_stay_in_range() {
+        let melted = [JokerType::IceCream as u8, 0, 20, 0, 0, 0];
+        assert!(Joker::from_bytes(melted).is_some());
+        let overflowed = [JokerType::IceCream as u8, 0, 21, 0, 0, 0];
+        assert!(Joker::from_bytes(overflowed).is_none());
+
+        // An Eternal Ice Cream outlives its chips without wrapping around
+        let mut ice_cream = Joker::from_bytes(melted).unwrap();
+        ice_cream.add_sticker(Sticker::Eternal);
+        let view = GameView {
+            hand: PokerHand::HighCard,
+            contained: Default::default(),
+            played: &[],
+            held: &[],
+            deck_size: 0,
+            money: 0,
+            discards: 0,
+            hands: 0,
         };
-        [ Texture { buf, uv: CARD_UV0, colors },
-          Texture { buf, uv: CARD_UV1, colors } ]
+        ice_cream.on_event(&JokerEvent::HandScored, &view);
+        assert_eq!(ice_cream.counter, 20);
+        // Nothing left to add
+        assert!(ice_cream.independent(&view).is_none());
+    }
+
+    #[test]
+    fn rolled_stickers_are_never_eternal_and_perishable() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut seen = BitFlags::<Sticker>::empty();
+        for _ in 0..200 {
+            let mut joker = Joker::new(JokerType::Jimbo);
+            joker.roll_stickers(&mut rng, Stake::Gold);
+            assert!(!joker.stickers.contains(Sticker::Eternal | Sticker::Perishable));
+            if joker.stickers.contains(Sticker::Perishable) {
+                assert_eq!(joker.rounds_left, PERISHABLE_ROUNDS);
+            }
+            seen |= joker.stickers;
+        }
+        assert_eq!(seen, BitFlags::all());
+    }
+
+    #[test]
+    fn stickers_follow_the_stake() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut seen = [BitFlags::<Sticker>::empty(); 3];
+        for _ in 0..200 {
+            for (seen, stake) in seen.iter_mut().zip([Stake::Blue, Stake::Orange, Stake::Gold]) {
+                let mut joker = Joker::new(JokerType::Jimbo);
+                joker.roll_stickers(&mut rng, stake);
+                *seen |= joker.stickers;
+            }
+            let mut joker = Joker::new(JokerType::Jimbo);
+            joker.roll_stickers(&mut rng, Stake::Green);
+            assert!(joker.stickers.is_empty());
+        }
+        assert_eq!(seen, [
+            Sticker::Eternal.into(),
+            Sticker::Eternal | Sticker::Perishable,
+            BitFlags::all(),
+        ]);
     }
 }
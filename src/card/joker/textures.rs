@@ -1 +1,138 @@
-pub const JIMBO: [u8; 1200] = [ 0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xc0,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x00,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xf0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xf0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xf0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0xf0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0x30,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x03,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x03,0xff,0xff,0xff,0xff,0xff,0xf0,0x00,0x3f,0xff,0xff,0xff,0xff,0xff,0xfc,0x30,0xff,0xff,0xff,0xff,0xff,0xc5,0x55,0x4f,0xff,0xff,0xff,0xff,0xff,0xf0,0xfc,0x3f,0xff,0xff,0xff,0xff,0x15,0x55,0x53,0xff,0xff,0xff,0xff,0xff,0xf0,0xfc,0x3f,0xff,0xff,0xff,0xfc,0x55,0x54,0x03,0xff,0xff,0xff,0xff,0xff,0xf0,0xfc,0x3f,0xff,0xff,0xff,0xf1,0x55,0x53,0xfc,0xff,0xff,0xff,0xff,0xff,0xf0,0xfc,0x3f,0xff,0xff,0xff,0xf1,0x55,0x53,0xfc,0xff,0xff,0xff,0xff,0xff,0xfc,0x30,0xff,0xff,0xff,0xff,0xc5,0x55,0x53,0xfc,0xff,0xff,0xff,0xff,0xff,0xff,0x03,0xff,0xff,0xff,0xff,0xc5,0x55,0x50,0xf3,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x15,0x55,0x54,0x0f,0xff,0xff,0xff,0xff,0xff,0xfc,0x0c,0x0f,0xff,0xc0,0x0f,0x15,0x55,0x54,0xf0,0x03,0xff,0xff,0xff,0xff,0xfc,0x30,0xff,0xfc,0x2a,0xa0,0x55,0x55,0x55,0x0a,0xa8,0x3f,0xff,0xff,0xff,0xfc,0x03,0xff,0xf2,0xaa,0xaa,0x55,0x55,0x55,0xaa,0xaa,0x8f,0xff,0xff,0xff,0xfc,0x00,0xff,0xf2,0xaa,0xa9,0x55,0x55,0x55,0x6a,0xaa,0x8f,0xff,0xff,0xff,0xfc,0x3c,0x3f,0xca,0xaa,0xa9,0x55,0x55,0x55,0x6a,0xaa,0xa3,0xff,0xff,0xff,0xfc,0x3c,0x3f,0xca,0xaa,0xa5,0x55,0x55,0x55,0x5a,0xaa,0xa3,0xff,0xff,0xff,0xfc,0x0c,0x0f,0x2a,0x8a,0xa5,0x55,0x55,0x55,0x5a,0xa2,0xa8,0xff,0xff,0xff,0xff,0xff,0xff,0x2a,0x32,0xa5,0x55,0x55,0x55,0x5a,0x8c,0xa8,0xff,0xff,0xff,0xfc,0x00,0x3f,0x2a,0x3c,0x95,0x55,0x55,0x55,0x56,0x3c,0xa8,0xff,0xff,0xff,0xfc,0x3c,0x3f,0x28,0xfc,0x95,0x55,0xff,0x55,0x56,0x3f,0x28,0xff,0xff,0xff,0xfc,0x3f,0xfc,0xff,0x3c,0x55,0xff,0xff,0xff,0x55,0x3c,0xff,0x3f,0xff,0xff,0xfc,0x03,0xfc,0xff,0x31,0x56,0xaf,0xff,0xfa,0x95,0x4c,0xff,0x3f,0xff,0xff,0xfc,0x3f,0xff,0x3c,0xf1,0x5a,0xaa,0xff,0xaa,0xa5,0x4f,0x3c,0xff,0xff,0xff,0xfc,0x3c,0x3f,0xc3,0xf1,0x5a,0xbe,0xbe,0xbe,0xa5,0x4f,0xc3,0xff,0xff,0xff,0xfc,0x00,0x3f,0xff,0xf1,0x7e,0xd7,0xbe,0xd7,0xbd,0x4f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xf1,0x7f,0xaa,0xff,0xaa,0xfd,0x4f,0xff,0xff,0xff,0xff,0xfc,0x00,0xff,0xff,0xfc,0x7f,0xff,0xff,0xff,0xfd,0x3f,0xff,0xff,0xff,0xff,0xfc,0x3c,0x3f,0xff,0xfc,0xff,0xff,0xbe,0xff,0xff,0x3f,0xff,0xfc,0x3c,0x3f,0xfc,0x3c,0x3f,0xff,0xf3,0xf7,0xff,0xeb,0xff,0xdf,0xcf,0xff,0xfc,0x3c,0x3f,0xfc,0x3c,0x3f,0xff,0xf3,0xf5,0x5f,0xff,0xf5,0x5f,0xcf,0xff,0xff,0x00,0x3f,0xfc,0x00,0xff,0xff,0xfc,0xfd,0x55,0x55,0x55,0x7f,0x3f,0xff,0xfc,0x3c,0x3f,0xfc,0x3c,0x3f,0xff,0xff,0x3f,0x57,0xff,0xd5,0xfc,0xff,0xff,0xfc,0x3c,0x3f,0xfc,0x3c,0x3f,0xff,0xff,0xcf,0x55,0xff,0x55,0xf3,0xff,0xff,0xfc,0x3c,0x3f,0xff,0xff,0xff,0xff,0xff,0xcf,0xd5,0x55,0x57,0xf3,0xff,0xff,0xff,0x00,0x3f,0xff,0xff,0xff,0xff,0xff,0xf3,0xf5,0x55,0x5f,0xcf,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0x0f,0xff,0x55,0xff,0xf0,0x0f,0xff,0xfc,0x00,0x3f,0xff,0xff,0xff,0xff,0xc2,0xab,0xff,0xff,0xff,0xea,0xa3,0xff,0xfc,0x3c,0x3f,0xff,0xff,0xff,0xfc,0x2a,0xaa,0xff,0xff,0xff,0xaa,0xa8,0x3f,0xff,0xfc,0x3f,0xff,0xff,0xff,0xc2,0xaa,0xaa,0xef,0xff,0xfa,0xbe,0xaa,0x83,0xff,0x00,0x3f,0xff,0xff,0xff,0x2a,0xaa,0xaf,0xaa,0xae,0xaa,0xaa,0xea,0xa8,0xff,0xfc,0x3f,0xff,0xff,0xfc,0xaa,0xaa,0xea,0xaa,0xab,0xaa,0xaa,0xaa,0xaa,0x3c,0x3c,0x3f,0xff,0xff,0xf2,0xaa,0xab,0xaa,0xaa,0xaa,0xea,0xaa,0xab,0xaa,0x8c,0x00,0x3f,0xff,0xff,0xf2,0xaa,0xae,0xaa,0xaa,0xaa,0xea,0xaa,0xaa,0xaa,0x8f,0xff,0xff,0xff,0xff,0xf2,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0x8c,0x30,0x3f,0xff,0xff,0xf2,0xaa,0xba,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0xaa,0x8c,0x3c,0x3f,0xff,0xff,0xf2,0xaa,0x8a,0xaa,0xaa,0xaa,0xaa,0xaa,0xa2,0xaa,0x3c,0x3c,0x3f,0xff,0xff,0xfc,0xaa,0x32,0xaa,0xaa,0x82,0xaa,0xaa,0x8c,0xa8,0xff,0x00,0x3f,0xff,0xff,0xf5,0x00,0xf2,0xaa,0xaa,0x3c,0xaa,0xaa,0x8f,0x00,0x3f,0xc0,0x3f,0xff,0xff,0xf5,0x57,0xf2,0xaa,0xa8,0xff,0x2a,0xaa,0x8f,0xc0,0x3f,0x0c,0x3f,0xff,0xff,0xfd,0x5f,0xf2,0xaa,0xa3,0xff,0xca,0xaa,0x8f,0xf0,0xf0,0x30,0x3f,0xff,0xff,0xff,0xff,0xf2,0xaa,0x8f,0xff,0xf2,0xaa,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0xaa,0x3f,0xff,0xfc,0xa8,0x7f,0xff,0xff,0xc0,0xff,0xff,0xff,0xff,0xff,0xf0,0x28,0xff,0xff,0xff,0x01,0x5f,0xff,0xff,0x0c,0x3f,0xff,0xff,0xff,0xff,0xf0,0x03,0xff,0xff,0xff,0xd5,0x5f,0xff,0xfc,0x3f,0x0f,0xff,0xff,0xff,0xff,0xf0,0x03,0xff,0xff,0xff,0xd5,0x5f,0xff,0xfc,0x3f,0x0f,0xff,0xff,0xff,0xff,0xfc,0x0f,0xff,0xff,0xff,0xf5,0x7f,0xff,0xfc,0x3f,0x0f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0x3f,0x0f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0c,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xc0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xc0,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0c,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0f,0x3f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0x0f,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0x00,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xfc,0x03,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff,0xff ];
+use wasm4::draw::DrawIndex;
+
+use crate::card::{poker::PokerHand, suit_into_buffer, Edition};
+use crate::gfx::texture::{Canvas, Texture, TextureColors, TEXTURE_WIDTH};
+
+use super::{Joker, JokerType, Sticker};
+
+include!(concat!(env!("OUT_DIR"), "/emblems.rs"));
+
+/// Palette of the hand drawn 2bpp art
+pub const ART_COLORS: TextureColors = TextureColors::TwoBpp([
+    DrawIndex::Second,
+    DrawIndex::First,
+    DrawIndex::Third,
+    DrawIndex::Fourth,
+]);
+
+/// Jokers without hand drawn art: a jester head with an emblem underneath
+/// telling them apart. Mult jokers are red, chip jokers blue.
+pub fn draw(joker: &Joker) -> [Texture; 2] {
+    let mut canvas = Canvas::new();
+    let kind = joker.kind;
+
+    canvas.rect([0, 0], [60, 80]);
+    jester(&mut canvas, is_scary(kind));
+
+    if let Some(suit) = kind.suit() {
+        suit_into_buffer(canvas.bits(), suit, 25 + TEXTURE_WIDTH * 54);
+    }
+    if let Some(hand) = kind.contains() {
+        hand_into_canvas(&mut canvas, hand);
+    }
+    emblem(kind, &mut canvas);
+    marks_into_canvas(&mut canvas, joker);
+
+    canvas.finish(TextureColors::OneBpp([DrawIndex::Fourth, ink(kind)]))
+}
+
+/// Hand drawn art with the marks drawn over a copy of it
+pub fn over_art(joker: &Joker, art: &[u8; 1200]) -> [Texture; 2] {
+    // Outline and background of the art
+    let mut canvas = Canvas::over(art, 0, 3);
+    marks_into_canvas(&mut canvas, joker);
+    canvas.finish(ART_COLORS)
+}
+
+/// One glyph per sticker down the top left corner, debuffed jokers get
+/// crossed out and editions a striped top right corner like on playing
+/// cards. Negatives show through the palette instead.
+fn marks_into_canvas(canvas: &mut Canvas, joker: &Joker) {
+    if joker.edition.is_some_and(|edition| edition != Edition::Negative) {
+        for y in 2..10 {
+            for x in 50..58 {
+                if (x + y) % 3 == 0 {
+                    canvas.set(x, y);
+                }
+            }
+        }
+    }
+    for (i, sticker) in joker.stickers.iter().enumerate() {
+        let [x, y] = [3, 3 + i as i32 * 9];
+        // Art can have its corner taken already
+        canvas.erase([x - 1, y - 1], [9, 10]);
+        match sticker {
+            // Padlock
+            Sticker::Eternal => {
+                canvas.rect([x + 1, y], [5, 4]);
+                canvas.fill([x, y + 3], [7, 4]);
+            }
+            // Hourglass
+            Sticker::Perishable => {
+                canvas.line([x, y], [x + 6, y]);
+                canvas.line([x, y + 7], [x + 6, y + 7]);
+                canvas.line([x + 1, y + 1], [x + 5, y + 6]);
+                canvas.line([x + 5, y + 1], [x + 1, y + 6]);
+            }
+            // Price tag
+            Sticker::Rental => {
+                canvas.rect([x, y + 1], [7, 6]);
+                canvas.line([x + 3, y], [x + 3, y + 7]);
+            }
+        }
+    }
+    if joker.is_debuffed() {
+        canvas.line([0, 0], [59, 79]);
+        canvas.line([59, 0], [0, 79]);
+    }
+}
+
+fn jester(canvas: &mut Canvas, scary: bool) {
+    // Hat with a bell on both points
+    canvas.line([14, 22], [20, 8]);
+    canvas.line([20, 8], [30, 18]);
+    canvas.line([30, 18], [40, 8]);
+    canvas.line([40, 8], [46, 22]);
+    canvas.line([14, 22], [46, 22]);
+    canvas.circle([20, 6], 3);
+    canvas.circle([40, 6], 3);
+
+    canvas.circle([30, 32], 10);
+    if scary {
+        canvas.line([24, 28], [28, 30]);
+        canvas.line([36, 28], [32, 30]);
+        for x in (24..36).step_by(3) {
+            canvas.line([x, 37], [x + 1, 35]);
+            canvas.line([x + 1, 35], [x + 3, 37]);
+        }
+    } else {
+        canvas.fill([26, 29], [2, 2]);
+        canvas.fill([33, 29], [2, 2]);
+        canvas.line([25, 35], [27, 37]);
+        canvas.line([27, 37], [33, 37]);
+        canvas.line([33, 37], [35, 35]);
+    }
+}
+
+/// Small cards laid out the way the poker hand looks
+fn hand_into_canvas(canvas: &mut Canvas, hand: PokerHand) {
+    let card = |canvas: &mut Canvas, x: i32, y: i32, filled: bool| match filled {
+        true => canvas.fill([x, y], [7, 10]),
+        false => canvas.rect([x, y], [7, 10]),
+    };
+    match hand {
+        PokerHand::Pair => {
+            card(canvas, 22, 56, true);
+            card(canvas, 31, 56, true);
+        }
+        PokerHand::Three => (0..3).for_each(|i| card(canvas, 17 + i * 9, 56, true)),
+        PokerHand::TwoPair => {
+            card(canvas, 12, 56, true);
+            card(canvas, 20, 56, true);
+            card(canvas, 33, 56, false);
+            card(canvas, 41, 56, false);
+        }
+        PokerHand::Straight => (0..5).for_each(|i| card(canvas, 8 + i * 9, 64 - i * 3, false)),
+        _ => (0..5).for_each(|i| card(canvas, 8 + i * 9, 56, true)),
+    }
+}
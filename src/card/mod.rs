@@ -2,6 +2,8 @@ pub mod deck;
 pub mod state;
 pub mod animations;
 pub mod joker;
+pub mod consumable;
+pub mod poker;
 
 mod card;
 pub use card::*;
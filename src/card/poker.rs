@@ -0,0 +1,134 @@
+use enumflags2::BitFlags;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+
+// NOTE: Should replace with a bitflag for "Contains" type jokers
+#[repr(u16)]
+#[enumflags2::bitflags]
+#[derive(Debug, PartialEq, Eq, EnumIter, EnumCount, Copy, Clone)]
+pub enum PokerHand {
+    HighCard,
+    Pair,
+    TwoPair,
+    Three,
+    Straight,
+    Flush,
+    FullHouse,
+    Four,
+    StraightFlush,
+    Five,
+    FlushHouse,
+    FlushFive,
+}
+impl PokerHand {
+    /// Highest ranking hand among the ones a played hand contains
+    pub fn best(hands: BitFlags<PokerHand>) -> Self {
+        PokerHand::iter()
+            .rev()
+            .find(|hand| hands.contains(*hand))
+            .unwrap_or(PokerHand::HighCard)
+    }
+    /// Discriminants are bit values, this is the position in the enum
+    fn index(&self) -> usize {
+        (*self as u16).trailing_zeros() as usize
+    }
+    pub fn min_cards(&self) -> usize {
+        match self {
+            PokerHand::HighCard => 1,
+            PokerHand::Pair => 2,
+            PokerHand::Three => 3,
+            PokerHand::TwoPair | PokerHand::Four => 4,
+            PokerHand::Straight
+            | PokerHand::Flush
+            | PokerHand::FullHouse
+            | PokerHand::StraightFlush
+            | PokerHand::Five
+            | PokerHand::FlushHouse
+            | PokerHand::FlushFive
+            => 5,
+        }
+    }
+    /// Short enough to fit the HUD
+    pub fn name(&self) -> &'static str {
+        match self {
+            PokerHand::HighCard => "High",
+            PokerHand::Pair => "Pair",
+            PokerHand::TwoPair => "2 Pair",
+            PokerHand::Three => "3 Kind",
+            PokerHand::Straight => "Straight",
+            PokerHand::Flush => "Flush",
+            PokerHand::FullHouse => "Full H.",
+            PokerHand::Four => "4 Kind",
+            PokerHand::StraightFlush => "St Flush",
+            PokerHand::Five => "5 Kind",
+            PokerHand::FlushHouse => "Fl House",
+            PokerHand::FlushFive => "Fl Five",
+        }
+    }
+    /// Chips and mult at level 1
+    fn base(&self) -> (u32, u32) {
+        match self {
+            PokerHand::HighCard => (5, 1),
+            PokerHand::Pair => (10, 2),
+            PokerHand::TwoPair => (20, 2),
+            PokerHand::Three => (30, 3),
+            PokerHand::Straight => (30, 4),
+            PokerHand::Flush => (35, 4),
+            PokerHand::FullHouse => (40, 4),
+            PokerHand::Four => (60, 7),
+            PokerHand::StraightFlush => (100, 8),
+            PokerHand::Five => (120, 12),
+            PokerHand::FlushHouse => (140, 14),
+            PokerHand::FlushFive => (160, 16),
+        }
+    }
+    /// Chips and mult added by every level past the first
+    fn per_level(&self) -> (u32, u32) {
+        match self {
+            PokerHand::HighCard => (10, 1),
+            PokerHand::Pair => (15, 1),
+            PokerHand::TwoPair => (20, 1),
+            PokerHand::Three => (20, 2),
+            PokerHand::Straight => (30, 3),
+            PokerHand::Flush => (15, 2),
+            PokerHand::FullHouse => (25, 2),
+            PokerHand::Four => (30, 3),
+            PokerHand::StraightFlush => (40, 4),
+            PokerHand::Five => (35, 3),
+            PokerHand::FlushHouse => (40, 4),
+            PokerHand::FlushFive => (50, 3),
+        }
+    }
+}
+
+/// Level of every poker hand, raised by planet cards
+pub struct HandLevels([u8; PokerHand::COUNT]);
+
+impl HandLevels {
+    pub fn level(&self, hand: PokerHand) -> u8 {
+        self.0[hand.index()]
+    }
+    pub fn level_up(&mut self, hand: PokerHand) {
+        self.0[hand.index()] = self.0[hand.index()].saturating_add(1);
+    }
+    pub fn save(&self) -> [u8; PokerHand::COUNT] {
+        self.0
+    }
+    /// `None` for a level of 0, every hand starts at 1
+    pub fn load(bytes: &[u8]) -> Option<Self> {
+        let levels: [u8; PokerHand::COUNT] = bytes.try_into().ok()?;
+        levels.iter().all(|level| *level > 0).then_some(Self(levels))
+    }
+    /// Base chips and mult of a hand at its current level
+    pub fn score(&self, hand: PokerHand) -> (f32, f32) {
+        let (chips, mult) = hand.base();
+        let (chips_per, mult_per) = hand.per_level();
+        let levels = self.level(hand) as u32 - 1;
+        ((chips + chips_per * levels) as f32, (mult + mult_per * levels) as f32)
+    }
+}
+
+impl Default for HandLevels {
+    fn default() -> Self {
+        Self([1; PokerHand::COUNT])
+    }
+}
@@ -12,20 +12,30 @@ use crate::util::MouseCompound;
 use crate::animator::animation_state::AnimationState;
 use crate::gfx::{Render, Triangle, TriangleFill, Vectorize};
 
-use super::animations::idle1;
+use super::animations::{idle1, shatter};
 use super::card::Card;
+use super::consumable::{Planet, Spectral, Tarot};
+use super::deck::DeckType;
 use super::joker::Joker;
 
 #[derive(Debug)]
 pub enum CardData {
     Playing(Card),
     Joker(Joker),
+    Tarot(Tarot),
+    Planet(Planet),
+    Spectral(Spectral),
+    Back(DeckType),
 }
 impl CardData {
     pub fn texture(&self) -> [Texture; 2] {
         match self {
             CardData::Playing(card) => card.texture(),
             CardData::Joker(joker) => joker.texture(),
+            CardData::Tarot(tarot) => tarot.texture(),
+            CardData::Planet(planet) => planet.texture(),
+            CardData::Spectral(spectral) => spectral.texture(),
+            CardData::Back(deck) => deck.texture(),
         }
     }
 }
@@ -38,6 +48,7 @@ pub struct CardState {
     diff_vecs: [[f32; 2]; 4],
 
     animation: Option<AnimationState>,
+    shattered: bool,
 }
 
 impl CardState {
@@ -53,6 +64,7 @@ impl CardState {
                 [-16.0,  21.0],
             ],
             animation,
+            shattered: false,
         }
     }
     fn animate(&self) -> Mat3 {
@@ -94,6 +106,39 @@ impl CardState {
     pub fn card(&self) -> &CardData {
         &self.card
     }
+
+    pub fn card_mut(&mut self) -> &mut CardData {
+        &mut self.card
+    }
+
+    pub fn set_card(&mut self, card: CardData) {
+        self.card = card;
+    }
+
+    pub fn into_card(self) -> CardData {
+        self.card
+    }
+
+    /// Plays the shatter animation, the owner should drop the card once
+    /// [`CardState::is_gone`] returns true
+    pub fn shatter(&mut self) {
+        self.shattered = true;
+        self.set_animation(shatter());
+    }
+
+    pub fn is_shattered(&self) -> bool {
+        self.shattered
+    }
+
+    pub fn is_gone(&self) -> bool {
+        self.shattered && !self.is_animating()
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation
+            .as_ref()
+            .is_some_and(|anim| !anim.finished())
+    }
 }
 
 impl CardState {
@@ -136,14 +181,17 @@ impl InputHandler for CardState {
 impl MessageHandler for CardState {
     fn handle_message(&mut self, rx: &crate::message::Reader) {
         match rx.read() {
-            Some(Message::CardHovered(id)) if id == self.id => {
+            Some(Message::CardHovered(id)) if id == self.id && !self.shattered => {
                 match &self.card {
-                    CardData::Playing(_) => self.set_animation(AnimationState::new(
+                    CardData::Playing(_) | CardData::Back(_) => self.set_animation(AnimationState::new(
                         &[Scale::new([1.3, 1.3], [1.0, 1.0]).into()],
                         Duration::from_secs(0.1),
                         Some(idle1),
                     )),
-                    CardData::Joker(_) => self.set_animation(AnimationState::new(
+                    CardData::Joker(_)
+                    | CardData::Tarot(_)
+                    | CardData::Planet(_)
+                    | CardData::Spectral(_) => self.set_animation(AnimationState::new(
                         &[Scale::new([2.0, 2.0], [1.0, 1.0]).into()],
                         Duration::from_secs(0.1),
                         Some(idle1),
@@ -1,3 +1,4 @@
+use bitvec::{order::Msb0, slice::BitSlice, view::AsMutBits};
 use wasm4::draw::DrawIndex;
 
 use crate::__heap_base;
@@ -18,8 +19,145 @@ pub enum TextureColors {
     TwoBpp([DrawIndex; 4]),
 }
 
+impl TextureColors {
+    /// Light and dark swapped, blue and red swapped
+    pub fn inverted(self) -> Self {
+        let invert = |index: DrawIndex| match index {
+            DrawIndex::First => DrawIndex::Third,
+            DrawIndex::Second => DrawIndex::Fourth,
+            DrawIndex::Third => DrawIndex::First,
+            DrawIndex::Fourth => DrawIndex::Second,
+            DrawIndex::Transparent => DrawIndex::Transparent,
+        };
+        match self {
+            TextureColors::OneBpp(colors) => TextureColors::OneBpp(colors.map(invert)),
+            TextureColors::TwoBpp(colors) => TextureColors::TwoBpp(colors.map(invert)),
+        }
+    }
+}
+
 type UV = [[f32; 2]; 3];
 pub const CARD_UV0: UV = [ [0.0, 0.0], [1.0, 0.0], [0.0, 1.0] ];
 pub const CARD_UV1: UV = [ [1.0, 0.0], [1.0, 1.0], [0.0, 1.0] ];
 
 pub const TEXTURE_BUFFER: *mut [u8; 1200] = &raw mut __heap_base as *mut [u8; 1200];
+
+/// Bits per pixel of a [`Canvas`]
+enum Depth {
+    One,
+    /// Pixel values drawn with and erased to
+    Two { ink: u8, paper: u8 },
+}
+
+/// Drawing surface over [`TEXTURE_BUFFER`] for procedurally drawn cards,
+/// 1bpp unless it starts out from 2bpp art
+pub struct Canvas {
+    buf: &'static mut [u8; 1200],
+    depth: Depth,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        let buf = unsafe { TEXTURE_BUFFER.as_mut() }.unwrap();
+        buf.fill(0);
+        Self { buf, depth: Depth::One }
+    }
+
+    /// 2bpp surface holding a copy of `art` to draw over
+    pub fn over(art: &[u8; 1200], ink: u8, paper: u8) -> Self {
+        let buf = unsafe { TEXTURE_BUFFER.as_mut() }.unwrap();
+        buf.copy_from_slice(art);
+        Self { buf, depth: Depth::Two { ink, paper } }
+    }
+
+    pub fn bits(&mut self) -> &mut BitSlice<u8, Msb0> {
+        self.buf.as_mut_bits::<Msb0>()
+    }
+
+    pub fn set(&mut self, x: i32, y: i32) {
+        self.plot(x, y, true);
+    }
+
+    /// Clears the area back to the background
+    pub fn erase(&mut self, start: [i32; 2], size: [i32; 2]) {
+        for y in start[1]..start[1] + size[1] {
+            for x in start[0]..start[0] + size[0] {
+                self.plot(x, y, false);
+            }
+        }
+    }
+
+    fn plot(&mut self, x: i32, y: i32, ink: bool) {
+        if x < 0 || y < 0 || x >= TEXTURE_WIDTH as i32 || y >= TEXTURE_HEIGHT as i32 {
+            return;
+        }
+        let pos = x as usize + y as usize * TEXTURE_WIDTH;
+        match self.depth {
+            Depth::One => self.bits().set(pos, ink),
+            // Low bit first, see the 2bpp sampling in `gfx`
+            Depth::Two { ink: on, paper } => {
+                let value = if ink { on } else { paper };
+                self.bits().set(pos * 2, value & 1 != 0);
+                self.bits().set(pos * 2 + 1, value & 2 != 0);
+            }
+        }
+    }
+
+    pub fn line(&mut self, from: [i32; 2], to: [i32; 2]) {
+        let dx = (to[0] - from[0]).abs();
+        let dy = -(to[1] - from[1]).abs();
+        let sx = (to[0] - from[0]).signum();
+        let sy = (to[1] - from[1]).signum();
+
+        let [mut x, mut y] = from;
+        let mut err = dx + dy;
+        loop {
+            self.set(x, y);
+            if x == to[0] && y == to[1] {
+                break;
+            }
+            let e2 = err * 2;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn rect(&mut self, start: [i32; 2], size: [i32; 2]) {
+        let end = [start[0] + size[0] - 1, start[1] + size[1] - 1];
+        self.line(start, [end[0], start[1]]);
+        self.line([end[0], start[1]], end);
+        self.line(end, [start[0], end[1]]);
+        self.line([start[0], end[1]], start);
+    }
+
+    pub fn fill(&mut self, start: [i32; 2], size: [i32; 2]) {
+        for y in start[1]..start[1] + size[1] {
+            for x in start[0]..start[0] + size[0] {
+                self.set(x, y);
+            }
+        }
+    }
+
+    pub fn circle(&mut self, center: [i32; 2], radius: i32) {
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let d = x * x + y * y;
+                if d <= radius * radius && d > (radius - 1) * (radius - 1) {
+                    self.set(center[0] + x, center[1] + y);
+                }
+            }
+        }
+    }
+
+    pub fn finish(self, colors: TextureColors) -> [Texture; 2] {
+        let buf: &'static [u8] = self.buf;
+        [ Texture { buf, uv: CARD_UV0, colors },
+          Texture { buf, uv: CARD_UV1, colors } ]
+    }
+}
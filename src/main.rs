@@ -1,11 +1,14 @@
-#![no_std]
-#![no_main]
+// Unit tests run on the host with std, see `cargo test-host`
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
+#![cfg_attr(test, allow(dead_code))]
 
 #![allow(static_mut_refs)]
 #![feature(debug_closure_helpers)]
 
+#[cfg(not(test))]
 #[panic_handler]
-fn panic_handler(info: &PanicInfo) -> ! {
+fn panic_handler(info: &core::panic::PanicInfo) -> ! {
     tracef!("Panic: {:?}", info);
     unreachable!()
 }
@@ -18,11 +21,15 @@ mod util;
 mod scene;
 mod message;
 mod linalg;
+// The joker data parser build.rs runs, here for its tests
+#[cfg(test)]
+#[path = "../build/jokers.rs"]
+mod joker_data;
 
-use core::{mem::MaybeUninit, panic::PanicInfo};
-use card::state::CardState;
+use core::mem::MaybeUninit;
+use card::{deck::Deck, state::CardState, Card};
 use message::{Message, MessageBuffer, MessageHandler, Reader};
-use scene::{Demo, Menu, Scene, ScenePtr, DEMO};
+use scene::{Demo, Menu, Run, Scene, ScenePtr, DECK_VIEW, DEMO};
 use util::{Entropy, FrameCounter, MouseCompound};
 use wasm4::{self as w4, control::{Mouse, MouseState}, draw::{Color, Framebuffer}, tracef};
 
@@ -44,7 +51,9 @@ impl w4::rt::Runtime for Blazen {
 
         tracef!("Hello {}!", "logger");
         tracef!("__heap_base: {:?}", &raw const __heap_base);
+        tracef!("sizeof Card: {}", size_of::<Card>());
         tracef!("sizeof CardState: {}", size_of::<CardState>());
+        tracef!("sizeof Deck: {}", size_of::<Deck>());
 
         tracef!("sizeof Demo: {}", size_of::<Demo>());
         tracef!("DEMO: {:?}", DEMO);
@@ -89,10 +98,18 @@ impl w4::rt::Runtime for Blazen {
 impl MessageHandler for Blazen {
     fn handle_message(&mut self, rx: &Reader) {
         match rx.read() {
-            Some(Message::Start) => {
-                DEMO.init();
+            Some(Message::Start(deck)) => {
+                DEMO.init(Run::New(deck));
                 self.scene = DEMO.get();
             }
+            Some(Message::Continue) => {
+                DEMO.init(Run::Saved);
+                self.scene = DEMO.get();
+            }
+            Some(Message::DeckClicked) => {
+                DECK_VIEW.init(unsafe { DEMO.as_ref() }.unwrap().deck());
+                self.scene = DECK_VIEW.get();
+            }
             Some(Message::BackToGame) => self.scene = DEMO.get(),
             _ => (),
         }
@@ -103,4 +120,5 @@ unsafe extern "C" {
     static mut __heap_base: u8;
 }
 
+#[cfg(not(test))]
 w4::main! { Blazen }
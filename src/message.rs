@@ -2,7 +2,7 @@ use core::cell::Cell;
 
 use wasm4::tracef;
 
-use crate::util::MouseCompound;
+use crate::{card::{deck::DeckType, SortOrder}, util::MouseCompound};
 
 pub trait InputHandler {
     fn handle_input(&self, mouse: &MouseCompound, tx: &mut Writer);
@@ -16,12 +16,21 @@ pub trait MessageHandler {
 pub enum Message {
     CardHovered(usize),
     CardClicked(usize),
+    /// Held card moved, with the mouse x
+    CardDragged(usize, i32),
+    CardDropped(usize),
     // Menu
-    Start,
+    Start(DeckType),
+    Continue,
     // Demo
     DeckClicked,
+    DeckHovered,
     PlayHand,
     DiscardHand,
+    SortHand(SortOrder),
+    UseConsumable(usize),
+    SellConsumable(usize),
+    SellJoker(usize),
     // DeckScene
     BackToGame,
 }
@@ -1,28 +1,125 @@
-use crate::message::{Message, MessageHandler, Reader, Writer};
+use heapless::Vec;
+use strum::IntoEnumIterator;
+use wasm4::{draw::{DrawIndex, Framebuffer}, format::format_no_std, tracef};
+
+use crate::{button::Button, FORMAT_BUF, MouseCompound};
+use crate::message::{InputHandler, Message, MessageHandler, Reader, Writer};
 use crate::gfx::Render;
-use crate::card::{deck::Deck, state::CardState, Suit};
+use crate::card::{deck::Deck, Card, Rank, Suit};
 
-use super::{Scene, DEMO};
+use super::{Scene, ScenePtr, DEMO};
 
 pub const DECK_VIEW: *mut DeckScene = DEMO.wrapping_add(1) as *mut DeckScene;
+
+impl ScenePtr for *mut DeckScene {
+    type Init = &'static Deck;
+
+    fn init(self, deck: &'static Deck) {
+        unsafe {
+            self.write(DeckScene::new(deck));
+            tracef!("Deck view initialized");
+        }
+    }
+    fn get(self) -> &'static mut dyn Scene {
+        unsafe {self.as_mut()}.unwrap()
+    }
+}
+
+// Size of a card in the grid
+const CARD_WIDTH: i32 = 11;
+const CARD_HEIGHT: i32 = 18;
+// Cards start after the suit letter
+const ROW_START: i32 = 12;
+
+/// Every card the player owns, a row per suit sorted by rank. Cards no
+/// longer in the draw pile are greyed out.
 pub struct DeckScene {
-    page: heapless::Vec<CardState, 64>,
+    // Card and whether it's still in the draw pile
+    cards: Vec<(Card, bool), 256>,
+    remaining: usize,
+    back: Button,
 }
+
 impl DeckScene {
-    pub fn get() -> &'static mut Self {
-        unsafe {
-            DECK_VIEW.as_mut().unwrap()
+    fn new(deck: &Deck) -> Self {
+        // Indexed by card id
+        let mut in_draw_pile = [false; 256];
+        deck.draw_pile().for_each(|card| in_draw_pile[card.id() as usize] = true);
+        let mut cards: Vec<(Card, bool), 256> = deck
+            .iter()
+            .map(|card| (*card, in_draw_pile[card.id() as usize]))
+            .collect();
+        // Highest rank first, like a hand sorted by rank
+        cards.sort_unstable_by_key(|(card, _)| (card.suit() as u8, Rank::Ace as u8 - card.rank() as u8));
+
+        Self {
+            cards,
+            remaining: deck.remaining(),
+            back: Button::new(
+                [62, 140],
+                "Back",
+                DrawIndex::Third,
+                DrawIndex::Second,
+                Message::BackToGame,
+            ),
+        }
+    }
+    fn render_row(&self, fb: &Framebuffer, suit: Suit, y: i32) {
+        let row: Vec<&(Card, bool), 256> = self.cards
+            .iter()
+            .filter(|(card, _)| card.suit() == suit)
+            .collect();
+        let ink = match suit {
+            Suit::Spade | Suit::Club => DrawIndex::Second,
+            Suit::Heart | Suit::Diamond => DrawIndex::Third,
+        };
+        let letter = match suit {
+            Suit::Spade => "S",
+            Suit::Heart => "H",
+            Suit::Club => "C",
+            Suit::Diamond => "D",
+        };
+        fb.text(letter, [2, y + 5], ink, DrawIndex::Transparent);
+
+        // Rows longer than 13 overlap to stay on screen
+        let spacing = match row.len() as i32 {
+            0 | 1 => CARD_WIDTH,
+            n => ((158 - ROW_START - CARD_WIDTH) / (n - 1)).min(CARD_WIDTH),
+        };
+        for (i, (card, in_deck)) in row.into_iter().enumerate() {
+            let x = ROW_START + i as i32 * spacing;
+            let (outline, text) = match in_deck {
+                true => (DrawIndex::Second, ink),
+                false => (DrawIndex::First, DrawIndex::First),
+            };
+            let rank = match card.rank() {
+                Rank::Two => "2",
+                Rank::Three => "3",
+                Rank::Four => "4",
+                Rank::Five => "5",
+                Rank::Six => "6",
+                Rank::Seven => "7",
+                Rank::Eight => "8",
+                Rank::Nine => "9",
+                Rank::Ten => "T",
+                Rank::Jack => "J",
+                Rank::Queen => "Q",
+                Rank::King => "K",
+                Rank::Ace => "A",
+            };
+            fb.rect([x, y], [CARD_WIDTH as u32, CARD_HEIGHT as u32], DrawIndex::Fourth, outline);
+            fb.text(rank, [x + 2, y + 5], text, DrawIndex::Transparent);
         }
     }
 }
 
 impl Scene for DeckScene {
-    fn update(&mut self, mouse: &crate::MouseSemaphore, tx: &mut Writer, _rx: &Reader) {
-        if let Some(m) = mouse.state() {
-            if m.buttons.left && !mouse.prev().buttons.left {
-                tx.write(Message::BackToGame).ok();
-            }
-        }
+    fn update(&mut self) {}
+}
+
+impl InputHandler for DeckScene {
+    fn handle_input(&self, mouse: &MouseCompound, tx: &mut Writer) {
+        self.back.handle_input(mouse, tx);
     }
 }
 
@@ -31,26 +128,17 @@ impl MessageHandler for DeckScene {
 }
 
 impl Render for DeckScene {
-    fn render(&self, fb: &wasm4::draw::Framebuffer) {
-        self.page.iter().for_each(|spade| spade.render(fb));
-    }
-}
-
-impl From<&Deck> for &'static mut dyn Scene {
-    fn from(value: &Deck) -> Self {
-        let this = DeckScene::get();
-        let spade_n = value.iter()
-            .filter(move |card| card.suit() == Suit::Spade)
-            .count();
+    fn render(&self, fb: &Framebuffer) {
+        fb.rect([0, 0], [160, 11], DrawIndex::Third, DrawIndex::Second);
+        fb.text(format_no_std::show(
+            unsafe { FORMAT_BUF.assume_init_mut() },
+            format_args!("Deck {}/{}", self.remaining, self.cards.len()),
+        ).unwrap(), [2, 2], DrawIndex::Fourth, DrawIndex::Transparent);
 
-        for (i, spade) in value
-            .iter()
-            .filter(move |card| card.suit() == Suit::Spade)
-            .enumerate() {
-            let x = 70 + (i * 160 / spade_n - 50);
-            this.page.push(CardState::new(i, spade.clone(), [x as i32, 20], None)).ok();
+        for (i, suit) in Suit::iter().enumerate() {
+            self.render_row(fb, suit, 16 + i as i32 * (CARD_HEIGHT + 12));
         }
 
-        this
+        self.back.render(fb);
     }
 }
@@ -0,0 +1,105 @@
+use core::fmt::Write as _;
+
+use heapless::{Deque, String};
+use wasm4::draw::{DrawIndex, Framebuffer};
+
+use crate::{card::joker::Effect, gfx::Render};
+
+/// How long every callout stays up
+const CALLOUT_FRAMES: u32 = 20;
+
+struct Callout {
+    text: String<12>,
+    // Center of the text
+    origin: [i32; 2],
+    // Joker the callout comes from
+    source: Option<usize>,
+}
+
+/// Short texts popping up over cards while a hand is scored, shown one after
+/// another
+#[derive(Default)]
+pub struct Callouts {
+    queue: Deque<Callout, 32>,
+    frames: u32,
+}
+
+impl Callouts {
+    /// Texts past the queue size are dropped, the score doesn't depend on them
+    pub fn push(&mut self, text: &str, origin: [i32; 2]) {
+        self.push_from(text, origin, None);
+    }
+    /// What an effect did, like "+4 Mult", "X2" or "+$3". The joker it
+    /// comes from wobbles along, if any.
+    pub fn push_effect(&mut self, effect: &Effect, origin: [i32; 2], source: Option<usize>) {
+        let mut text: String<12> = String::new();
+        match effect {
+            Effect::Numbers(numbers) if numbers.multx != 1.0 => {
+                write!(text, "X{:.2}", numbers.multx).ok();
+                // X1.50 reads better as X1.5 and X2.00 as X2
+                while text.ends_with('0') {
+                    text.pop();
+                }
+                if text.ends_with('.') {
+                    text.pop();
+                }
+            }
+            Effect::Numbers(numbers) if numbers.mult > 0 => {
+                write!(text, "+{} Mult", numbers.mult).ok();
+            }
+            Effect::Numbers(numbers) => {
+                write!(text, "+{} Chips", numbers.points).ok();
+            }
+            Effect::Money(money) => {
+                write!(text, "+${}", money).ok();
+            }
+            Effect::Destroy => return,
+        }
+        self.push_from(&text, origin, source);
+    }
+    fn push_from(&mut self, text: &str, origin: [i32; 2], source: Option<usize>) {
+        let mut callout = Callout { text: String::new(), origin, source };
+        // Truncated rather than lost
+        for c in text.chars() {
+            if callout.text.push(c).is_err() {
+                break;
+            }
+        }
+        self.queue.push_back(callout).ok();
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+    /// Advances the front callout, nothing is shown until this is called.
+    /// Returns the joker a callout comes from on its first frame, so it can
+    /// be animated along.
+    pub fn update(&mut self) -> Option<usize> {
+        let callout = self.queue.front()?;
+        self.frames += 1;
+        let source = match self.frames {
+            1 => callout.source,
+            _ => None,
+        };
+        if self.frames > CALLOUT_FRAMES {
+            self.queue.pop_front();
+            self.frames = 0;
+        }
+        source
+    }
+}
+
+impl Render for Callouts {
+    fn render(&self, fb: &Framebuffer) {
+        let Some(callout) = self.queue.front() else { return };
+        if self.frames == 0 {
+            return;
+        }
+        let width = callout.text.len() as i32 * 8;
+        let start = [
+            (callout.origin[0] - width / 2).clamp(0, 160 - width - 2),
+            callout.origin[1] - 5,
+        ];
+        fb.rect(start, [width as u32 + 2, 10], DrawIndex::Third, DrawIndex::Second);
+        fb.text(callout.text.as_str(), [start[0] + 1, start[1] + 1], DrawIndex::Fourth, DrawIndex::Transparent);
+    }
+}
@@ -0,0 +1,514 @@
+use heapless::Vec;
+use rand::{seq::{IteratorRandom, SliceRandom}, Rng};
+use strum::IntoEnumIterator;
+use wasm4::{draw::DrawIndex, tracef};
+
+use crate::{button::Button, gfx::Render, util::MouseCompound};
+use crate::message::{InputHandler, Message, MessageHandler, Reader, Writer};
+use crate::card::{
+    animations::idle1,
+    consumable::{Consumable, ConsumableEffect, Planet, Tarot},
+    deck::HAND_CAPACITY,
+    joker::JokerEvent,
+    poker::PokerHand,
+    state::{CardData, CardState},
+    Card, Edition, Enhancement, Suit,
+};
+
+use super::Demo;
+
+/// Area to the right of the jokers row
+const AREA_START: i32 = 112;
+const AREA_WIDTH: i32 = 48;
+
+/// Card count and two bytes per card
+pub const CONSUMABLES_SAVE_SIZE: usize = 1 + 4 * 2;
+
+/// Tarot, planet and spectral cards waiting to be used
+pub struct Consumables {
+    size: usize,
+    cards: Vec<CardState, 4>,
+    // Slot showing its Use/Sell buttons
+    active: Option<usize>,
+    buttons: Option<[Button; 2]>,
+}
+
+impl Consumables {
+    pub fn add(&mut self, card: CardData) -> Result<(), CardData> {
+        if self.len() >= self.size {
+            return Err(card);
+        }
+        let id = 0x200 + self.cards.len();
+        let origin = [AREA_START + AREA_WIDTH / 2, 44];
+        self.cards
+            .push(CardState::new(id, card, origin, Some(idle1())))
+            .map_err(CardState::into_card)?;
+        self.layout();
+        // Keep the buttons next to the card they belong to
+        if let Some(pos) = self.active {
+            self.activate(pos);
+        }
+        Ok(())
+    }
+    /// Slots taken, cards on their way out don't count
+    pub fn len(&self) -> usize {
+        self.cards.iter().filter(|card| !card.is_shattered()).count()
+    }
+    pub fn get(&self, pos: usize) -> Option<Consumable> {
+        self.cards
+            .get(pos)
+            .filter(|card| !card.is_shattered())
+            .and_then(|card| card.card().consumable())
+    }
+    /// Used or sold, the card shatters and frees its slot
+    pub fn remove(&mut self, pos: usize) {
+        if let Some(card) = self.cards.get_mut(pos) {
+            card.shatter();
+        }
+        self.deactivate();
+    }
+    pub fn save(&self) -> Vec<u8, CONSUMABLES_SAVE_SIZE> {
+        let mut bytes = Vec::new();
+        bytes.push(self.len() as u8).unwrap();
+        (0..self.cards.len())
+            .filter_map(|pos| self.get(pos))
+            .for_each(|consumable| bytes.extend_from_slice(&consumable.to_bytes()).unwrap());
+        bytes
+    }
+    /// `None` if the save is corrupted or holds more than fits the slots
+    pub fn load(bytes: &[u8]) -> Option<Self> {
+        let (len, body) = bytes.split_first()?;
+        let mut consumables = Self::default();
+        for chunk in body.get(..*len as usize * 2)?.chunks_exact(2) {
+            let consumable = Consumable::from_bytes([chunk[0], chunk[1]])?;
+            consumables.add(consumable.into()).ok()?;
+        }
+        Some(consumables)
+    }
+    fn activate(&mut self, pos: usize) {
+        let [x, _] = self.cards[pos].origin();
+        let left = x - 16;
+        self.active = Some(pos);
+        self.buttons = Some([
+            Button::new(
+                [left - 27, 30],
+                "Use",
+                DrawIndex::Third,
+                DrawIndex::Second,
+                Message::UseConsumable(pos),
+            ),
+            Button::new(
+                [left - 35, 44],
+                "Sell",
+                DrawIndex::First,
+                DrawIndex::Fourth,
+                Message::SellConsumable(pos),
+            ),
+        ]);
+    }
+    fn deactivate(&mut self) {
+        self.active = None;
+        self.buttons = None;
+    }
+    fn layout(&mut self) {
+        let n = self.cards.len() as i32;
+        let spacing = match n {
+            0 | 1 => 0,
+            n => ((AREA_WIDTH - 32) / (n - 1)).min(32),
+        };
+        let start = AREA_START + (AREA_WIDTH - spacing * (n - 1)) / 2;
+        self.cards
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, card)| {
+                card.set_origin([start + i as i32 * spacing, 44]);
+                card.set_id(0x200 + i);
+            });
+    }
+    pub fn update(&mut self) {
+        self.cards
+            .iter_mut()
+            .for_each(CardState::update);
+
+        if self.cards.iter().any(CardState::is_gone) {
+            self.cards.retain(|card| !card.is_gone());
+            self.layout();
+        }
+    }
+}
+
+impl Default for Consumables {
+    fn default() -> Self {
+        Self {
+            size: 2,
+            cards: Vec::new(),
+            active: None,
+            buttons: None,
+        }
+    }
+}
+
+impl InputHandler for Consumables {
+    fn handle_input(&self, mouse: &MouseCompound, tx: &mut Writer) {
+        // Buttons are drawn over the cards, they get the click first
+        if let Some(buttons) = &self.buttons {
+            buttons
+                .iter()
+                .for_each(|button| button.handle_input(mouse, tx));
+        }
+        self.cards
+            .iter()
+            .for_each(|card| card.handle_input(mouse, tx));
+    }
+}
+
+impl MessageHandler for Consumables {
+    fn handle_message(&mut self, rx: &Reader) {
+        self.cards
+            .iter_mut()
+            .for_each(|card| card.handle_message(rx));
+
+        if let Some(Message::CardClicked(id)) = rx.read() {
+            if !(0x200..0x300).contains(&id) {
+                return;
+            }
+            let pos = id - 0x200;
+            match self.active {
+                Some(active) if active == pos => self.deactivate(),
+                _ if self.get(pos).is_some() => self.activate(pos),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Render for Consumables {
+    fn render(&self, fb: &wasm4::draw::Framebuffer) {
+        self.cards
+            .iter()
+            .for_each(|card| card.render(fb));
+        if let Some(buttons) = &self.buttons {
+            buttons
+                .iter()
+                .for_each(|button| button.render(fb));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UseError {
+    NotUsable,
+    /// Wrong number of hand cards selected
+    Selection,
+}
+
+impl Demo {
+    /// Applies a consumable to the currently selected hand cards. Consumables
+    /// it creates are handed back for the caller to find room for.
+    pub fn use_consumable(&mut self, card: &CardData) -> Result<Vec<CardData, 2>, UseError> {
+        let Some(consumable) = card.consumable() else {
+            return Err(UseError::NotUsable);
+        };
+        if !consumable.selection().contains(&self.hand.selected.len()) {
+            return Err(UseError::Selection);
+        }
+        let effects = consumable.effects();
+        if matches!(effects[0], ConsumableEffect::CopyLastUsed) && self.last_used.is_none() {
+            return Err(UseError::NotUsable);
+        }
+
+        // Deck ids of the selected cards, left to right in hand
+        let mut positions = self.hand.selected.clone();
+        positions.sort_unstable();
+        let ids: Vec<u8, 5> = positions
+            .iter()
+            .map(|pos| match self.hand.cards[*pos].card() {
+                CardData::Playing(card) => card.id(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        let mut created = Vec::new();
+        for effect in effects {
+            self.apply(effect, &ids, &mut created);
+        }
+
+        if consumable != Consumable::Tarot(Tarot::Fool) {
+            self.last_used = Some(consumable);
+        }
+        self.trigger(JokerEvent::ConsumableUsed(consumable));
+
+        self.hand.selected.clear();
+        self.hand.sync(&self.deck, &mut self.rng);
+        self.update_preview();
+
+        Ok(created)
+    }
+
+    fn apply(&mut self, effect: ConsumableEffect, ids: &[u8], created: &mut Vec<CardData, 2>) {
+        // Cards held in hand, for the effects that don't care about selection
+        let held: Vec<u8, HAND_CAPACITY> = self.deck.hand().map(Card::id).take(HAND_CAPACITY).collect();
+
+        match effect {
+            ConsumableEffect::Enhance(enhancement) => ids.iter().for_each(|id| {
+                self.deck.transform(*id, |card| card.enhance(enhancement));
+            }),
+            ConsumableEffect::ConvertSuit(suit) => ids.iter().for_each(|id| {
+                self.deck.transform(*id, |card| card.set_suit(suit));
+            }),
+            ConsumableEffect::RankUp => ids.iter().for_each(|id| {
+                self.deck.transform(*id, |card| card.set_rank(card.rank().next()));
+            }),
+            ConsumableEffect::Destroy => ids.iter().for_each(|id| {
+                self.deck.destroy(*id);
+            }),
+            ConsumableEffect::Copy => {
+                let right = *self.deck.card(ids[1]).unwrap();
+                self.deck.transform(ids[0], |card| card.copy_from(&right));
+            }
+            ConsumableEffect::CreateTarots(n) => (0..n).for_each(|_| {
+                created.push(CardData::Tarot(Tarot::random(&mut self.rng))).ok();
+            }),
+            ConsumableEffect::CreatePlanets(n) => (0..n).for_each(|_| {
+                created.push(CardData::Planet(Planet::random(&mut self.rng))).ok();
+            }),
+            ConsumableEffect::CreateJoker(rarity) => {
+                let joker = self.jokers.generate(&mut self.rng, rarity);
+                if self.jokers.add(joker).is_err() {
+                    tracef!("No room for joker");
+                }
+            }
+            ConsumableEffect::CopyLastUsed => {
+                created.push(self.last_used.unwrap().into()).ok();
+            }
+            ConsumableEffect::DoubleMoney { max } => {
+                self.money += self.money.min(max);
+            }
+            ConsumableEffect::JokerSellValue { max } => {
+                let value = self.jokers.iter().map(|(_, joker)| joker.sell_value()).sum::<u32>();
+                self.money += value.min(max);
+            }
+            ConsumableEffect::JokerEdition { odds } => {
+                let editions = [Edition::Foil, Edition::Holographic, Edition::Polychrome];
+                let edition = editions[self.rng.gen_range(0..editions.len())];
+                let hit = self.rng.gen_range(0..odds) == 0;
+                let joker = self.jokers
+                    .iter_mut()
+                    .filter(|(_, joker)| joker.edition().is_none())
+                    .choose(&mut self.rng);
+                match joker {
+                    Some((_, joker)) if hit => joker.set_edition(edition),
+                    _ => tracef!("Nope!"),
+                }
+            }
+            ConsumableEffect::LevelUp(hand) => self.levels.level_up(hand),
+            ConsumableEffect::LevelUpAll => {
+                PokerHand::iter().for_each(|hand| self.levels.level_up(hand));
+            }
+            ConsumableEffect::Seal(seal) => ids.iter().for_each(|id| {
+                self.deck.transform(*id, |card| card.set_seal(seal));
+            }),
+            ConsumableEffect::CardEdition => {
+                let editions = [Edition::Foil, Edition::Holographic, Edition::Polychrome];
+                let edition = editions[self.rng.gen_range(0..editions.len())];
+                ids.iter().for_each(|id| {
+                    self.deck.transform(*id, |card| card.set_edition(edition));
+                });
+            }
+            ConsumableEffect::DestroyRandom(n) => {
+                let mut held = held;
+                let (picked, _) = held.partial_shuffle(&mut self.rng, n as usize);
+                picked.iter().for_each(|id| {
+                    self.deck.destroy(*id);
+                });
+            }
+            ConsumableEffect::AddToHand { ranks, n } => {
+                for _ in 0..n {
+                    let suit = Suit::iter().choose(&mut self.rng).unwrap();
+                    let rank = *ranks.choose(&mut self.rng).unwrap();
+                    let mut card = Card::new(suit, rank);
+                    card.enhance(Enhancement::iter().choose(&mut self.rng).unwrap());
+                    match self.deck.add_to_hand(card) {
+                        Some(card) => self.trigger(JokerEvent::CardAdded(card)),
+                        None => tracef!("Deck is full"),
+                    }
+                }
+            }
+            ConsumableEffect::Duplicate(n) => ids.iter().for_each(|id| {
+                for _ in 0..n {
+                    if let Some(card) = self.deck.duplicate(*id) {
+                        self.trigger(JokerEvent::CardAdded(card));
+                    }
+                }
+            }),
+            ConsumableEffect::UnifySuit => {
+                let Some(pick) = held.choose(&mut self.rng) else {
+                    return;
+                };
+                let suit = self.deck.card(*pick).unwrap().suit();
+                held.iter().for_each(|id| {
+                    self.deck.transform(*id, |card| card.set_suit(suit));
+                });
+            }
+            ConsumableEffect::UnifyRank => {
+                let Some(pick) = held.choose(&mut self.rng) else {
+                    return;
+                };
+                let rank = self.deck.card(*pick).unwrap().rank();
+                held.iter().for_each(|id| {
+                    self.deck.transform(*id, |card| card.set_rank(rank));
+                });
+            }
+            ConsumableEffect::HandSize(change) => {
+                self.hand.size = self.hand.size.saturating_add_signed(change as isize).clamp(1, HAND_CAPACITY);
+            }
+            ConsumableEffect::Money(money) => self.money += money,
+            ConsumableEffect::SetMoney(money) => self.money = money,
+            ConsumableEffect::NegativeJoker => {
+                let joker = self.jokers
+                    .iter_mut()
+                    .filter(|(_, joker)| joker.edition().is_none())
+                    .choose(&mut self.rng);
+                if let Some((_, joker)) = joker {
+                    joker.set_edition(Edition::Negative);
+                }
+            }
+            ConsumableEffect::PolychromeJoker => {
+                let Some((keep, joker)) = self.jokers.iter_mut().choose(&mut self.rng) else {
+                    return;
+                };
+                joker.set_edition(Edition::Polychrome);
+                self.destroy_jokers_except(keep);
+            }
+            ConsumableEffect::CopyJoker => {
+                let Some((keep, joker)) = self.jokers.iter().choose(&mut self.rng) else {
+                    return;
+                };
+                let mut copy = joker.clone();
+                // Negative stays with the original, the copy would get a free slot
+                if copy.edition() == Some(Edition::Negative) {
+                    copy.remove_edition();
+                }
+                self.destroy_jokers_except(keep);
+                self.jokers.add(copy).ok();
+            }
+        }
+    }
+
+    fn destroy_jokers_except(&mut self, keep: usize) {
+        let others: Vec<usize, 10> = self.jokers
+            .iter()
+            .map(|(pos, _)| pos)
+            .filter(|pos| *pos != keep)
+            .collect();
+        others.into_iter().for_each(|pos| self.jokers.destroy(pos));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{consumable::Spectral, deck::DeckType, joker::{Joker, JokerType, Sticker}};
+    use crate::scene::demo::jokers::Jokers;
+
+    /// A run with a full hand and only `jokers` in play
+    fn demo(jokers: &[Joker]) -> Demo {
+        let mut demo = Demo::new(DeckType::default());
+        demo.jokers = Jokers::empty();
+        jokers.iter().for_each(|joker| demo.jokers.add(joker.clone()).unwrap());
+        demo.hand.fill(&mut demo.deck, &mut demo.rng);
+        demo
+    }
+
+    fn kinds(demo: &Demo) -> Vec<(JokerType, Option<Edition>), 10> {
+        demo.jokers.iter().map(|(_, joker)| (joker.kind(), joker.edition())).collect()
+    }
+
+    #[test]
+    fn ankh_copies_one_joker_and_destroys_the_rest() {
+        let mut demo = demo(&[Joker::new(JokerType::Jimbo), Joker::new(JokerType::Egg)]);
+        demo.use_consumable(&CardData::Spectral(Spectral::Ankh)).unwrap();
+
+        let kept = kinds(&demo);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0], kept[1]);
+    }
+
+    #[test]
+    fn ankh_copies_leave_negative_behind() {
+        let mut negative = Joker::new(JokerType::Jimbo);
+        negative.set_edition(Edition::Negative);
+        let mut demo = demo(&[negative]);
+        demo.use_consumable(&CardData::Spectral(Spectral::Ankh)).unwrap();
+
+        assert_eq!(kinds(&demo).as_slice(), [
+            (JokerType::Jimbo, Some(Edition::Negative)),
+            (JokerType::Jimbo, None),
+        ]);
+    }
+
+    #[test]
+    fn hex_keeps_one_polychrome_joker() {
+        let mut eternal = Joker::new(JokerType::Runner);
+        eternal.add_sticker(Sticker::Eternal);
+        let mut demo = demo(&[Joker::new(JokerType::Jimbo), Joker::new(JokerType::Egg), eternal]);
+        demo.use_consumable(&CardData::Spectral(Spectral::Hex)).unwrap();
+
+        // Eternal jokers survive whichever one was picked
+        let kept = kinds(&demo);
+        assert!(kept.iter().any(|joker| joker.1 == Some(Edition::Polychrome)));
+        assert!(kept.iter().any(|joker| joker.0 == JokerType::Runner));
+        assert!(kept.len() <= 2);
+    }
+
+    #[test]
+    fn ouija_and_ectoplasm_shrink_the_hand() {
+        let mut demo = demo(&[Joker::new(JokerType::Jimbo)]);
+        demo.use_consumable(&CardData::Spectral(Spectral::Ouija)).unwrap();
+        assert_eq!(demo.hand.size, 7);
+        // Every held card ends up the same rank
+        let ranks: Vec<_, HAND_CAPACITY> = demo.deck.hand().map(Card::rank).collect();
+        assert!(ranks.iter().all(|rank| *rank == ranks[0]));
+
+        demo.use_consumable(&CardData::Spectral(Spectral::Ectoplasm)).unwrap();
+        assert_eq!(demo.hand.size, 6);
+        assert_eq!(kinds(&demo).as_slice(), [(JokerType::Jimbo, Some(Edition::Negative))]);
+    }
+
+    #[test]
+    fn destroy_random_takes_from_the_hand() {
+        for (spectral, added) in [(Spectral::Familiar, 3), (Spectral::Grim, 2), (Spectral::Incantation, 4)] {
+            let mut demo = demo(&[]);
+            let (deck, hand) = (demo.deck.len(), demo.deck.hand().count());
+            demo.use_consumable(&CardData::Spectral(spectral)).unwrap();
+            assert_eq!(demo.deck.len(), deck - 1 + added);
+            // Whatever doesn't fit the hand goes to the draw pile
+            assert_eq!(demo.deck.hand().count(), (hand - 1 + added).min(HAND_CAPACITY));
+        }
+
+        let mut demo = demo(&[]);
+        let (deck, money) = (demo.deck.len(), demo.money);
+        demo.use_consumable(&CardData::Spectral(Spectral::Immolate)).unwrap();
+        assert_eq!(demo.deck.len(), deck - 5);
+        assert_eq!(demo.deck.hand().count(), demo.hand.size - 5);
+        assert_eq!(demo.money, money + 20);
+    }
+
+    #[test]
+    fn created_jokers_come_without_stickers() {
+        let cards = [
+            CardData::Tarot(Tarot::Judgement),
+            CardData::Spectral(Spectral::Wraith),
+            CardData::Spectral(Spectral::Soul),
+        ];
+        for card in cards {
+            for _ in 0..20 {
+                let mut demo = demo(&[]);
+                demo.use_consumable(&card).unwrap();
+                let (_, joker) = demo.jokers.iter().next().unwrap();
+                // Sticker flags are the fifth saved byte
+                assert_eq!(joker.to_bytes()[4], 0);
+            }
+        }
+    }
+}
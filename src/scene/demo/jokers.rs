@@ -1,38 +1,314 @@
-use crate::{gfx::Render, message::{InputHandler, MessageHandler}};
-use crate::card::{animations::idle1, joker::{Joker, JokerType}, state::{CardData, CardState}};
+use rand::Rng;
+use wasm4::{draw::DrawIndex, format::format_no_std};
+
+use crate::{button::Button, gfx::Render, message::{InputHandler, Message, MessageHandler}, util::Duration, FORMAT_BUF};
+use crate::animator::{animation_state::AnimationState, transform::Translate};
+use crate::card::{animations::{idle1, proc1}, Edition, joker::{CopyTarget, Joker, JokerType, Rarity, Stake}, state::{CardData, CardState}};
+
+/// Width of the row, the consumable slots sit to the right of it
+const ROW_WIDTH: i32 = 112;
+
+/// Joker count and six bytes per joker
+pub const JOKERS_SAVE_SIZE: usize = 1 + 10 * 6;
 
 pub struct Jokers {
     size: usize,
     pub jokers: heapless::Vec<CardState, 10>,
+    // Position of the joker under the mouse
+    hovered: Option<usize>,
+    // Joker showing its Sell button
+    active: Option<usize>,
+    sell_button: Option<Button>,
+    // Joker pressed with the mouse, and whether it's being dragged
+    held: Option<usize>,
+    dragging: bool,
 }
 
 impl Jokers {
+    /// Fails once every slot is taken, negatives bring their own slot
+    pub fn add(&mut self, joker: Joker) -> Result<(), Joker> {
+        if joker.edition() != Some(Edition::Negative) && self.len() >= self.slots() {
+            return Err(joker);
+        }
+        let id = 0x100 + self.jokers.len();
+        self.jokers
+            .push(CardState::new(id, CardData::Joker(joker), [ROW_WIDTH / 2, 44], Some(idle1())))
+            .map_err(|state| match state.into_card() {
+                CardData::Joker(joker) => joker,
+                _ => unreachable!(),
+            })?;
+        self.layout();
+        Ok(())
+    }
+    /// Jokers in order, counters, editions and stickers included
+    pub fn save(&self) -> heapless::Vec<u8, JOKERS_SAVE_SIZE> {
+        let mut bytes = heapless::Vec::new();
+        bytes.push(self.iter().count() as u8).unwrap();
+        self.iter()
+            .for_each(|(_, joker)| bytes.extend_from_slice(&joker.to_bytes()).unwrap());
+        bytes
+    }
+    /// Replaces the row with saved jokers, `None` if the save is corrupted
+    pub fn load(bytes: &[u8]) -> Option<Self> {
+        let (len, body) = bytes.split_first()?;
+        let mut jokers = Self::empty();
+        for chunk in body.get(..*len as usize * 6)?.chunks_exact(6) {
+            let joker = Joker::from_bytes(chunk.try_into().ok()?)?;
+            jokers.add(joker).ok()?;
+        }
+        Some(jokers)
+    }
+    /// Shatters the joker at `pos`, it's dropped once the animation ends.
+    /// Eternal jokers stay.
+    pub fn destroy(&mut self, pos: usize) {
+        if self.get(pos).is_none_or(Joker::is_eternal) {
+            return;
+        }
+        self.jokers[pos].shatter();
+        if self.active == Some(pos) {
+            self.deactivate();
+        }
+    }
+    /// Slots taken, jokers on their way out don't count
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+    /// Ages every joker by a round, returns the rent due
+    pub fn end_round(&mut self) -> u32 {
+        self.iter_mut().map(|(_, joker)| joker.end_round()).sum()
+    }
+    /// Draws a new joker, see [`JokerType::generate`]
+    pub fn generate<T: Rng>(&self, rng: &mut T, rarity: Option<Rarity>) -> Joker {
+        let owned: heapless::Vec<JokerType, 10> = self.iter().map(|(_, joker)| joker.kind()).collect();
+        Joker::new(JokerType::generate(rng, rarity, &owned))
+    }
+    /// Draws a joker for the shop, which higher stakes put stickers on
+    pub fn for_sale<T: Rng>(&self, rng: &mut T, stake: Stake) -> Joker {
+        let mut joker = self.generate(rng, None);
+        joker.roll_stickers(rng, stake);
+        joker
+    }
+    /// Every negative joker adds a slot
+    pub fn slots(&self) -> usize {
+        self.size + self.iter().filter(|(_, joker)| joker.edition() == Some(Edition::Negative)).count()
+    }
+    pub fn get(&self, pos: usize) -> Option<&Joker> {
+        self.iter().find(|(p, _)| *p == pos).map(|(_, joker)| joker)
+    }
+    /// Jokers still in play, in order, along with their position in the row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Joker)> {
+        self.jokers
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| !state.is_shattered())
+            .map(|(pos, state)| match state.card() {
+                CardData::Joker(joker) => (pos, joker),
+                _ => unreachable!(),
+            })
+    }
+    /// Joker whose ability the one at `pos` uses, itself unless it's a copy
+    /// joker. Copies are followed along the row, `None` when there's nothing
+    /// to copy or the copies go around in circles.
+    pub fn ability(&self, pos: usize) -> Option<&Joker> {
+        let row: heapless::Vec<(usize, &Joker), 10> = self.iter().collect();
+        let mut i = row.iter().position(|(p, _)| *p == pos)?;
+        // Any chain longer than the row has to visit a joker twice
+        for _ in 0..row.len() {
+            let (_, joker) = row[i];
+            // Debuffed copies have nothing to pass on
+            if joker.is_debuffed() {
+                return None;
+            }
+            match joker.copies() {
+                None => return Some(joker),
+                Some(CopyTarget::Right) => i += 1,
+                Some(CopyTarget::Leftmost) => i = 0,
+            }
+            if i >= row.len() {
+                return None;
+            }
+        }
+        None
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut Joker)> {
+        self.jokers
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, state)| !state.is_shattered())
+            .map(|(pos, state)| match state.card_mut() {
+                CardData::Joker(joker) => (pos, joker),
+                _ => unreachable!(),
+            })
+    }
+    fn activate(&mut self, pos: usize) {
+        let [x, _] = self.jokers[pos].origin();
+        self.active = Some(pos);
+        // Eternal jokers can't be sold
+        if self.get(pos).is_some_and(Joker::is_eternal) {
+            self.sell_button = None;
+            return;
+        }
+        self.sell_button = Some(Button::new(
+            [(x - 17).clamp(0, ROW_WIDTH - 35), 54],
+            "Sell",
+            DrawIndex::First,
+            DrawIndex::Fourth,
+            Message::SellJoker(pos),
+        ));
+    }
+    fn deactivate(&mut self) {
+        self.active = None;
+        self.sell_button = None;
+    }
+    /// Where the joker at `pos` rests in a row of `n`
+    fn slot(pos: usize, n: usize) -> [i32; 2] {
+        let n = n as i32;
+        // Cards start overlapping once they no longer fit side by side
+        let spacing = match n {
+            0 | 1 => 0,
+            n => ((ROW_WIDTH - 32) / (n - 1)).min(32),
+        };
+        let start = (ROW_WIDTH - spacing * (n - 1)) / 2;
+        [start + pos as i32 * spacing, 44]
+    }
+    fn layout(&mut self) {
+        let n = self.jokers.len();
+        self.jokers
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, joker)| {
+                joker.set_origin(Self::slot(i, n));
+                joker.set_id(0x100 + i);
+            });
+    }
+    /// Slides the joker at `pos` from wherever it's drawn back to its slot
+    fn settle(&mut self, pos: usize) {
+        let slot = Self::slot(pos, self.jokers.len());
+        let joker = &mut self.jokers[pos];
+        let [x, y] = joker.origin();
+        joker.set_origin(slot);
+        joker.set_animation(AnimationState::new(
+            &[Translate::new([(x - slot[0]) as f32, (y - slot[1]) as f32], [0.0, 0.0]).into()],
+            Duration::from_secs(0.1),
+            Some(idle1),
+        ));
+    }
+    /// Moves the joker at `from` to `to`, the jokers in between slide aside
+    /// to make room. The moved joker stays where it's drawn.
+    fn reorder(&mut self, from: usize, to: usize) {
+        let joker = self.jokers.remove(from);
+        self.jokers.insert(to, joker).unwrap();
+        for pos in 0..self.jokers.len() {
+            self.jokers[pos].set_id(0x100 + pos);
+            if pos != to && self.jokers[pos].origin() != Self::slot(pos, self.jokers.len()) {
+                self.settle(pos);
+            }
+        }
+    }
+    /// Shakes the joker at `pos` as its effect fires
+    pub fn wobble(&mut self, pos: usize) {
+        if let Some(joker) = self.jokers.get_mut(pos).filter(|joker| !joker.is_shattered()) {
+            joker.set_animation(proc1());
+        }
+    }
+    /// A held joker gets every mouse input until it's let go
+    pub fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
     pub fn update(&mut self) {
         self.jokers
             .iter_mut()
             .for_each(CardState::update);
+
+        if self.jokers.iter().any(CardState::is_gone) {
+            self.jokers.retain(|joker| !joker.is_gone());
+            self.layout();
+            // Positions moved, the button would point at another joker
+            self.deactivate();
+            self.held = None;
+            self.dragging = false;
+        }
+    }
+}
+
+impl Jokers {
+    /// Panel under the hovered joker: name, rarity, what it does right now
+    /// and what it sells for
+    fn render_info(&self, fb: &wasm4::draw::Framebuffer) {
+        let Some(pos) = self.hovered else { return };
+        let Some(joker) = self.get(pos) else { return };
+
+        let mut description = [0; 64];
+        let description = joker.description(&mut description);
+        // Copy jokers tell what they're copying
+        let copying = match joker.copies() {
+            None => "",
+            Some(_) => match self.ability(pos) {
+                Some(source) => source.kind().name(),
+                None => "Nothing",
+            },
+        };
+        let text = format_no_std::show(
+            unsafe { FORMAT_BUF.assume_init_mut() },
+            format_args!(
+                "{}\n{}\n{}\n{}{}{}Sell ${}",
+                joker.kind().name(),
+                joker.kind().rarity().name(),
+                description,
+                if copying.is_empty() { "" } else { "Copying:\n" },
+                copying,
+                if copying.is_empty() { "" } else { "\n" },
+                joker.sell_value(),
+            ),
+        ).unwrap();
+
+        let width = text.lines().map(str::len).max().unwrap_or(0) as i32 * 8 + 4;
+        let height = text.lines().count() as i32 * 8 + 4;
+        // Below the enlarged card, pushed back on screen where needed
+        let x = (self.jokers[pos].origin()[0] - width / 2).min(160 - width).max(0);
+        let y = 88.min(160 - height).max(0);
+        fb.rect([x, y], [width as u32, height as u32], DrawIndex::Second, DrawIndex::First);
+        fb.text(text, [x + 2, y + 2], DrawIndex::Fourth, DrawIndex::Transparent);
+    }
+    pub(super) fn empty() -> Self {
+        Self {
+            size: 5,
+            jokers: heapless::Vec::new(),
+            hovered: None,
+            active: None,
+            sell_button: None,
+            held: None,
+            dragging: false,
+        }
     }
 }
 
 impl Default for Jokers {
     fn default() -> Self {
-        let mut jokers = heapless::Vec::new(); 
+        let mut jokers = Self::empty();
 
         // For testing purposes
-        jokers.push(CardState::new(
-            // Jokers ids starting at 0x100
-            0x100,
-            CardData::Joker(Joker::new(JokerType::Jimbo)),
-            [80, 44],
-            Some(idle1()),
-        )).unwrap();
+        jokers.add(Joker::new(JokerType::Jimbo)).unwrap();
 
-        Self { size: 5, jokers }
+        jokers
     }
 }
 
 impl InputHandler for Jokers {
     fn handle_input(&self, mouse: &crate::util::MouseCompound, tx: &mut crate::message::Writer) {
+        if let Some(pos) = self.held {
+            let m = mouse.state();
+            if !m.buttons.left {
+                tx.write(Message::CardDropped(0x100 + pos)).ok();
+            } else if m.x != mouse.prev().x {
+                tx.write(Message::CardDragged(0x100 + pos, m.x as i32)).ok();
+            }
+            return;
+        }
+        // Drawn over the cards, gets the click first
+        if let Some(button) = &self.sell_button {
+            button.handle_input(mouse, tx);
+        }
         self.jokers
             .iter()
             .for_each(|joker| joker.handle_input(mouse, tx));
@@ -41,9 +317,53 @@ impl InputHandler for Jokers {
 
 impl MessageHandler for Jokers {
     fn handle_message(&mut self, rx: &crate::message::Reader) {
+        self.hovered = match rx.read() {
+            Some(Message::CardHovered(id)) if (0x100..0x100 + self.jokers.len()).contains(&id) => Some(id - 0x100),
+            _ => None,
+        };
         self.jokers
             .iter_mut()
             .for_each(|joker| joker.handle_message(rx));
+
+        match rx.read() {
+            Some(Message::CardClicked(id)) if (0x100..0x200).contains(&id) => {
+                let pos = id - 0x100;
+                match self.active {
+                    Some(active) if active == pos => self.deactivate(),
+                    _ if self.get(pos).is_some() => self.activate(pos),
+                    _ => (),
+                }
+                if self.get(pos).is_some() {
+                    self.held = Some(pos);
+                }
+            }
+            Some(Message::CardDragged(id, x)) if self.held.map(|pos| 0x100 + pos) == Some(id) => {
+                let pos = id - 0x100;
+                if !self.dragging {
+                    self.dragging = true;
+                    self.deactivate();
+                }
+                let x = x.clamp(16, ROW_WIDTH - 16);
+                self.jokers[pos].set_origin([x, 44]);
+                // Closest slot to the mouse
+                let n = self.jokers.len();
+                let to = (0..n)
+                    .min_by_key(|i| (Self::slot(*i, n)[0] - x).abs())
+                    .unwrap();
+                if to != pos {
+                    self.reorder(pos, to);
+                    self.held = Some(to);
+                }
+            }
+            Some(Message::CardDropped(id)) if self.held.map(|pos| 0x100 + pos) == Some(id) => {
+                if self.dragging {
+                    self.settle(id - 0x100);
+                }
+                self.held = None;
+                self.dragging = false;
+            }
+            _ => (),
+        }
     }
 }
 
@@ -52,5 +372,109 @@ impl Render for Jokers {
         self.jokers
             .iter()
             .for_each(|joker| joker.render(fb));
+        if let Some(button) = &self.sell_button {
+            button.render(fb);
+        }
+
+        let slots = format_no_std::show(
+            unsafe { FORMAT_BUF.assume_init_mut() },
+            format_args!("{}/{}", self.len(), self.slots()),
+        ).unwrap();
+        fb.rect([0, 57], [slots.len() as u32 * 8 + 3, 10], DrawIndex::Second, DrawIndex::Second);
+        fb.text(slots, [2, 58], DrawIndex::Fourth, DrawIndex::Transparent);
+
+        self.render_info(fb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use crate::card::joker::Sticker;
+
+    #[test]
+    fn save_round_trip() {
+        let mut jokers = Jokers::empty();
+        let mut runner = Joker::new(JokerType::Runner);
+        runner.add_sticker(Sticker::Perishable);
+        runner.add_sticker(Sticker::Rental);
+        runner.set_edition(Edition::Foil);
+        jokers.add(runner).unwrap();
+        // Six rounds old, counters are otherwise only built up by events
+        let egg = Joker::from_bytes([JokerType::Egg as u8, 0, 6, 0, 0, 0]).unwrap();
+        jokers.add(egg).unwrap();
+
+        let loaded = Jokers::load(&jokers.save()).unwrap();
+        let kept: heapless::Vec<[u8; 6], 10> = loaded.iter().map(|(_, joker)| joker.to_bytes()).collect();
+        let saved: heapless::Vec<[u8; 6], 10> = jokers.iter().map(|(_, joker)| joker.to_bytes()).collect();
+        assert_eq!(kept, saved);
+
+        let (_, runner) = loaded.iter().next().unwrap();
+        // Rentals go for $1
+        assert_eq!(runner.cost(), 1);
+        assert_eq!(runner.edition(), Some(Edition::Foil));
+        let (_, egg) = loaded.iter().nth(1).unwrap();
+        // Half of its $4 plus $3 a round
+        assert_eq!(egg.sell_value(), 20);
+    }
+
+    fn row(kinds: &[JokerType]) -> Jokers {
+        let mut jokers = Jokers::empty();
+        kinds.iter().for_each(|kind| jokers.add(Joker::new(*kind)).unwrap());
+        jokers
+    }
+
+    #[test]
+    fn shop_stickers_wait_for_higher_stakes() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(9);
+        let jokers = Jokers::empty();
+        for _ in 0..50 {
+            assert_eq!(jokers.for_sale(&mut rng, Stake::White).to_bytes()[4], 0);
+        }
+        assert!((0..50).any(|_| jokers.for_sale(&mut rng, Stake::Gold).to_bytes()[4] != 0));
+    }
+
+    #[test]
+    fn copies_follow_the_row() {
+        use JokerType::*;
+
+        let jokers = row(&[Blueprint, Blueprint, Jimbo, Brainstorm]);
+        assert_eq!(jokers.ability(0).map(Joker::kind), Some(Jimbo));
+        assert_eq!(jokers.ability(2).map(Joker::kind), Some(Jimbo));
+        // Brainstorm ends up where the leftmost Blueprint does
+        assert_eq!(jokers.ability(3).map(Joker::kind), Some(Jimbo));
+
+        // Nothing right of the last joker
+        assert!(row(&[Jimbo, Blueprint]).ability(1).is_none());
+    }
+
+    #[test]
+    fn copies_going_in_circles_do_nothing() {
+        use JokerType::*;
+
+        assert!(row(&[Brainstorm]).ability(0).is_none());
+        assert!(row(&[Blueprint, Brainstorm]).ability(0).is_none());
+    }
+
+    #[test]
+    fn debuffed_copies_do_nothing() {
+        let mut jokers = Jokers::empty();
+        // Perishable and out of rounds
+        let blueprint = [JokerType::Blueprint as u8, 0, 0, 0, Sticker::Perishable as u8, 0];
+        jokers.add(Joker::from_bytes(blueprint).unwrap()).unwrap();
+        jokers.add(Joker::new(JokerType::Jimbo)).unwrap();
+
+        assert!(jokers.ability(0).is_none());
+        assert_eq!(jokers.ability(1).map(Joker::kind), Some(JokerType::Jimbo));
+    }
+
+    #[test]
+    fn load_rejects_corrupted_saves() {
+        assert!(Jokers::load(&[]).is_none());
+        // Two jokers promised, one given
+        assert!(Jokers::load(&[2, 0, 0, 0, 0, 0, 0]).is_none());
+        // No such joker
+        assert!(Jokers::load(&[1, 0xFF, 0, 0, 0, 0, 0]).is_none());
     }
 }
@@ -1,26 +1,48 @@
-use crate::{card::{animations::random_idle, state::CardData, Rank}, util::Duration, CardState};
-use crate::animator::{animation_state::AnimationState, transform::{Rotate, Translate}};
+use crate::{card::{animations::{appear, idle1, random_idle}, consumable::{Consumable, Planet, Tarot}, joker::JokerEvent, poker::{HandLevels, PokerHand}, state::CardData, Card, Enhancement, Rank, Seal, SortOrder}, util::Duration, CardState};
+use crate::animator::{animation_state::AnimationState, transform::{Rotate, Scale, Translate}};
+use core::fmt::Write as _;
 use enumflags2::BitFlags;
+use strum::EnumCount as _;
+use callouts::Callouts;
+use consumables::Consumables;
 use jokers::Jokers;
+use scoring::Score;
 use rand::{rngs::SmallRng, RngCore as _, SeedableRng};
-use strum::{EnumIter, IntoEnumIterator};
 use wasm4::{draw::DrawIndex, format::format_no_std, tracef};
 
 use crate::{
-    button::Button, card::deck::Deck, Entropy, MouseCompound, FORMAT_BUF
+    button::Button, card::deck::{Deck, DeckType, HAND_CAPACITY}, Entropy, FrameCounter, MouseCompound, FORMAT_BUF
 };
 use crate::message::{InputHandler, Message, MessageHandler, Reader, Writer};
 use crate::gfx::{texture::TEXTURE_BUFFER, Render};
 use super::{Scene, ScenePtr};
 
 mod jokers;
+mod consumables;
+mod scoring;
+mod callouts;
+pub mod save;
 
 pub const DEMO: *mut Demo = TEXTURE_BUFFER.wrapping_add(1) as *mut Demo;
 
+/// What a run starts from
+#[derive(Debug, Clone, Copy)]
+pub enum Run {
+    New(DeckType),
+    /// The run on disk, see [`save`]
+    Saved,
+}
+
 impl ScenePtr for *mut Demo {
-    fn init(self) {
+    type Init = Run;
+
+    fn init(self, run: Run) {
+        let demo = match run {
+            Run::New(deck) => Demo::new(deck),
+            Run::Saved => Demo::resume(),
+        };
         unsafe {
-            self.write(Demo::new());
+            self.write(demo);
             tracef!("Demo initialized");
         }
     }
@@ -29,94 +51,6 @@ impl ScenePtr for *mut Demo {
     }
 }
 
-// NOTE: Should replace with a bitflag for "Contains" type jokers
-#[repr(u16)]
-#[enumflags2::bitflags]
-#[derive(EnumIter, Copy, Clone)]
-enum PokerHand {
-    HighCard,
-    Pair,
-    TwoPair,
-    Three,
-    Straight,
-    Flush,
-    FullHouse,
-    Four,
-    // StraightFlush,
-
-    Five,
-    // FlushHouse,
-    // FlushFive,
-}
-impl PokerHand {
-    fn min_cards(&self) -> usize {
-        match self {
-            PokerHand::HighCard => 1,
-            PokerHand::Pair => 2,
-            PokerHand::Three => 3,
-            PokerHand::TwoPair | PokerHand::Four => 4,
-            PokerHand::Straight
-            | PokerHand::Flush
-            | PokerHand::FullHouse
-            // | PokerHand::StraightFlush
-            | PokerHand::Five
-            // | PokerHand::FlushHouse
-            // | PokerHand::FlushFive
-            => 5,
-        }
-    }
-}
-struct Score {
-    points: f32,
-    mult: f32,
-}
-impl From<(f32, f32)> for Score {
-    fn from(value: (f32, f32)) -> Self {
-        Self { points: value.0, mult: value.1 }
-    }
-}
-impl From<BitFlags<PokerHand>> for Score {
-    fn from(value: BitFlags<PokerHand>) -> Self {
-        if value.contains(PokerHand::Flush) {
-            if value.contains(PokerHand::Straight) {
-                // Straight Flush
-                return (100.0, 8.0).into();
-            }
-            if value.contains(PokerHand::FullHouse) {
-                // Flush House
-                return (140.0, 14.0).into();
-            }
-            if value.contains(PokerHand::Five) {
-                // Flush Five
-                return (160.0, 16.0).into();
-            }
-        }
-        for hand in PokerHand::iter().rev() {
-            if value.contains(hand) {
-                return hand.into();
-            }
-        }
-        unreachable!()
-    }
-}
-impl From<PokerHand> for Score {
-    fn from(value: PokerHand) -> Self {
-        match value {
-            PokerHand::HighCard => (5.0, 1.0),
-            PokerHand::Pair => (10.0, 2.0),
-            PokerHand::TwoPair => (20.0, 2.0),
-            PokerHand::Three => (30.0, 3.0),
-            PokerHand::Straight => (30.0, 4.0),
-            PokerHand::Flush => (35.0, 4.0),
-            PokerHand::FullHouse => (40.0, 4.0),
-            PokerHand::Four => (60.0, 7.0),
-            // PokerHand::StraightFlush => (100.0, 8.0),
-            PokerHand::Five => (120.0, 12.0),
-            // PokerHand::FlushHouse => (140.0, 14.0),
-            // PokerHand::FlushFive => (160.0, 16.0),
-        }.into()
-    }
-}
 enum DemoState {
     Init,
     Idle,
@@ -132,10 +66,13 @@ pub struct Demo {
     deck_button: Button,
 
     jokers: Jokers,
+    consumables: Consumables,
     hand: HandState,
 
     play_button: Button,
     discard_button: Button,
+    rank_button: Button,
+    suit_button: Button,
 
     target: u32,
     score: u32,
@@ -145,14 +82,31 @@ pub struct Demo {
 
     max_discards: u8,
     rem_discards: u8,
+
+    money: u32,
+    // For The Fool
+    last_used: Option<Consumable>,
+
+    levels: HandLevels,
+    // Hand the current selection would play
+    preview: Option<PokerHand>,
+    // Blue seals make a planet for it at the end of the round
+    last_played: Option<PokerHand>,
+    // Glass cards that break once the hand is over
+    broken: heapless::Vec<u8, 5>,
+    callouts: Callouts,
+    // Deck button is hovered
+    deck_stats: bool,
 }
 impl Demo {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(deck: DeckType) -> Self {
+        let mut rng = SmallRng::from_seed(Entropy::get());
+        #[allow(unused_mut)]
+        let mut demo = Self {
             state: DemoState::Init,
-            rng: SmallRng::from_seed(Entropy::get()),
 
-            deck: Deck::new(),
+            deck: Deck::new(deck, &mut rng),
+            rng,
             deck_button: Button::new(
                 [125, 104],
                 "Deck",
@@ -174,18 +128,85 @@ impl Demo {
                 DrawIndex::Third,
                 Message::DiscardHand,
             ),
+            rank_button: Button::new(
+                [0, 104],
+                "Rank",
+                DrawIndex::First,
+                DrawIndex::Second,
+                Message::SortHand(SortOrder::Rank),
+            ),
+            suit_button: Button::new(
+                [36, 104],
+                "Suit",
+                DrawIndex::First,
+                DrawIndex::Second,
+                Message::SortHand(SortOrder::Suit),
+            ),
 
             target: 400,
             score: 0,
 
             jokers: Default::default(),
+            consumables: Default::default(),
             hand: Default::default(),
 
-            max_hands: 4,
-            max_discards: 3,
+            max_hands: 4 + deck.extra_hands(),
+            max_discards: 3 + deck.extra_discards(),
             rem_hands: Default::default(),
             rem_discards: Default::default(),
+
+            money: deck.starting_money(),
+            last_used: None,
+
+            levels: Default::default(),
+            preview: None,
+            last_played: None,
+            broken: heapless::Vec::new(),
+            callouts: Default::default(),
+            deck_stats: false,
+        };
+
+        // Something to try out right away, see the `test-cards` feature
+        #[cfg(feature = "test-cards")]
+        {
+            use crate::card::consumable::Spectral;
+
+            demo.consumables.add(CardData::Tarot(Tarot::Magician)).ok();
+            demo.consumables.add(CardData::Spectral(Spectral::random(&mut demo.rng))).ok();
         }
+
+        demo
+    }
+    /// Continues the run saved on disk from the start of a round, a new run
+    /// if the save can't be read
+    pub fn resume() -> Self {
+        let mut rng = SmallRng::from_seed(Entropy::get());
+        let Some(save) = save::restore(&mut rng) else {
+            tracef!("Save is unreadable, starting over");
+            return Self::new(DeckType::default());
+        };
+        let mut demo = Self::new(save.deck.kind());
+        demo.deck = save.deck;
+        demo.jokers = save.jokers;
+        demo.consumables = save.consumables;
+        demo.levels = save.levels;
+        demo.money = save.money;
+        demo.max_hands = save.max_hands;
+        demo.max_discards = save.max_discards;
+        demo.hand.size = save.hand_size;
+        demo.last_used = save.last_used;
+        demo
+    }
+}
+impl Demo {
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+    fn update_preview(&mut self) {
+        self.preview = match self.hand.selected.is_empty() {
+            true => None,
+            false => Some(PokerHand::best(self.hand.match_poker())),
+        };
     }
 }
 impl Scene for Demo {
@@ -194,67 +215,166 @@ impl Scene for Demo {
             DemoState::Init => {
                 tracef!("Shuffling deck");
                 self.rng = SmallRng::from_seed(Entropy::get());
-                self.deck.shuffle(&mut self.rng);
+                self.hand.cards.clear();
+                self.hand.selected.clear();
+                self.deck.reset(&mut self.rng);
                 self.hand.fill(&mut self.deck, &mut self.rng);
                 self.state = DemoState::Idle;
+                self.score = 0;
                 self.rem_discards = self.max_discards;
                 self.rem_hands = self.max_hands;
+                self.trigger(JokerEvent::BlindSelected);
             },
             DemoState::Idle => {
-                // Selection hold
-                self.hand.selected.iter().for_each(|i| {
-                    self.hand.cards[*i].set_animation(AnimationState::new(
-                        &[Translate::new([0.0, -10.0], [0.0, 0.0]).into()],
-                        Duration::from_secs(0.1),
-                        None,
-                    ));
-                });
+                // Selection hold, waits for a slide to finish since that
+                // keeps selected cards up already
+                if self.hand.is_settled() {
+                    self.hand.selected.iter().for_each(|i| {
+                        self.hand.cards[*i].set_animation(AnimationState::new(
+                            &[Translate::new([0.0, -10.0], [0.0, 0.0]).into()],
+                            Duration::from_secs(0.1),
+                            None,
+                        ));
+                    });
+                }
             },
             DemoState::InitPlay => { 
-                self.state = DemoState::Play(self.hand.match_poker().into());
+                let played: heapless::Vec<Card, 5> = self.hand.selected_cards().copied().collect();
+                let contained = self.hand.match_poker();
+                self.trigger(JokerEvent::HandPlayed { contained, played: &played });
+                let score = self.score_hand();
+                self.trigger(JokerEvent::HandScored);
+                self.state = DemoState::Play(score);
                 tracef!("Initialized Play");
             },
             DemoState::Play(score) => {
-                // for i in self.hand.selected.iter() {
-                //     let CardData::Playing(card) = self.hand.cards[*i].card() else { unreachable!() };
-                // }
+                let landed = self.hand.selected
+                    .iter()
+                    .all(|i| !self.hand.cards[*i].is_animating());
+                if landed && let Some(pos) = self.callouts.update() {
+                    self.jokers.wobble(pos);
+                }
+                if landed && self.callouts.is_empty() {
+                    if self.deck.kind().balances_score() {
+                        score.balance();
+                    }
+                    self.score += score.total();
+                    self.state = DemoState::HandEnd;
+                }
             }, 
-            DemoState::HandEnd => { },
+            DemoState::HandEnd => {
+                for card in self.hand.take_selected() {
+                    if self.broken.contains(&card.id()) {
+                        self.deck.destroy(card.id());
+                    } else {
+                        self.deck.play(&card);
+                    }
+                }
+                self.broken.clear();
+                if self.score >= self.target || self.rem_hands == 0 {
+                    tracef!("Round over, score: {}", self.score);
+                    self.money += 3 * self.hand
+                        .held_cards()
+                        .filter(|card| card.enhancement() == Some(Enhancement::Gold))
+                        .count() as u32;
+                    if let Some(hand) = self.last_played {
+                        let planet = Planet::from_hand(hand);
+                        for _ in self.hand.held_cards().filter(|card| card.seal() == Some(Seal::Blue)) {
+                            self.consumables.add(CardData::Planet(planet)).ok();
+                        }
+                    }
+                    self.trigger(JokerEvent::RoundEnd);
+                    let rent = self.jokers.end_round();
+                    self.money = self.money.saturating_sub(rent);
+                    save::store(self);
+                    self.state = DemoState::Init;
+                } else {
+                    self.hand.fill(&mut self.deck, &mut self.rng);
+                    self.state = DemoState::Idle;
+                }
+            },
         }
-        self.hand.cards
-            .iter_mut()
-            .for_each(CardState::update);
+        self.hand.update(&mut self.rng);
 
         self.jokers.update();
+        self.consumables.update();
     }
 }
 impl InputHandler for Demo {
     fn handle_input(&self, mouse: &MouseCompound, tx: &mut Writer) {
         if let DemoState::Play(_) = self.state { return };
+        if self.jokers.is_held() {
+            self.jokers.handle_input(mouse, tx);
+            return;
+        }
 
+        self.consumables.handle_input(mouse, tx);
         self.hand.handle_input(mouse, tx);
         self.jokers.handle_input(mouse, tx);
 
         self.deck_button.handle_input(mouse, tx);
         self.play_button.handle_input(mouse, tx);
         self.discard_button.handle_input(mouse, tx);
+        self.rank_button.handle_input(mouse, tx);
+        self.suit_button.handle_input(mouse, tx);
+        if self.deck_button.is_hovered(mouse.state()) {
+            tx.write(Message::DeckHovered).ok();
+        }
     }
 }
 impl MessageHandler for Demo {
     fn handle_message(&mut self, rx: &Reader) {
         self.hand.handle_message(rx);
         self.jokers.handle_message(rx);
+        self.consumables.handle_message(rx);
+        self.deck_stats = matches!(rx.read(), Some(Message::DeckHovered));
         if let DemoState::Idle = self.state {
             match rx.read() {
-                Some(Message::PlayHand) => {
+                Some(Message::CardClicked(hand_idx)) if hand_idx < 0xFF => self.update_preview(),
+                Some(Message::PlayHand) if self.rem_hands > 0 && self.hand.selected.len() > 0 => {
                     self.state = DemoState::InitPlay;
                     self.rem_hands -= 1;
+                    self.preview = None;
                 },
                 Some(Message::DiscardHand) if self.rem_discards > 0 && self.hand.selected.len() > 0 => {
                     self.rem_discards -= 1;
-                    self.hand.cards.retain(|card| !self.hand.selected.contains(&card.id()));
-                    self.hand.selected = heapless::Vec::new();
+                    let discarded = self.hand.take_selected();
+                    self.trigger(JokerEvent::Discard(&discarded));
+                    for card in discarded {
+                        if card.seal() == Some(Seal::Purple) {
+                            self.consumables.add(CardData::Tarot(Tarot::random(&mut self.rng))).ok();
+                        }
+                        self.deck.discard(&card);
+                    }
                     self.hand.fill(&mut self.deck, &mut self.rng);
+                    self.preview = None;
+                },
+                Some(Message::SortHand(sort)) => self.hand.sort(sort, &mut self.rng),
+                Some(Message::UseConsumable(pos)) => {
+                    let Some(consumable) = self.consumables.get(pos) else { return };
+                    match self.use_consumable(&consumable.into()) {
+                        Ok(created) => {
+                            self.consumables.remove(pos);
+                            for card in created {
+                                if self.consumables.add(card).is_err() {
+                                    tracef!("No room for consumable");
+                                }
+                            }
+                        }
+                        Err(err) => tracef!("Can't use {}: {:?}", consumable.name(), err),
+                    }
+                },
+                Some(Message::SellConsumable(pos)) => {
+                    let Some(consumable) = self.consumables.get(pos) else { return };
+                    self.money += consumable.sell_value();
+                    self.consumables.remove(pos);
+                },
+                Some(Message::SellJoker(pos)) => {
+                    let Some(joker) = self.jokers.get(pos).filter(|joker| !joker.is_eternal()) else { return };
+                    let kind = joker.kind();
+                    self.money += joker.sell_value();
+                    self.jokers.destroy(pos);
+                    self.trigger(JokerEvent::JokerSold(kind));
                 },
                 _ => (),
             }
@@ -277,6 +397,11 @@ impl Render for Demo {
             unsafe { FORMAT_BUF.assume_init_mut() },
             format_args!("Target: {}", self.target),
         ).unwrap(), [2, 13], DrawIndex::Fourth, DrawIndex::Transparent);
+        let money = format_no_std::show(
+            unsafe { FORMAT_BUF.assume_init_mut() },
+            format_args!("${}", self.money),
+        ).unwrap();
+        fb.text(money, [158 - money.len() as i32 * 8, 13], DrawIndex::Fourth, DrawIndex::Transparent);
 
         self.play_button.render(fb);
         fb.rect([125, 78], [35, 11], DrawIndex::Third, DrawIndex::Second);
@@ -293,18 +418,69 @@ impl Render for Demo {
         ).unwrap(), [2, 80], DrawIndex::Fourth, DrawIndex::Transparent);
         
         self.deck_button.render(fb);
+        self.rank_button.render(fb);
+        self.suit_button.render(fb);
+
+        if let Some(hand) = self.preview {
+            let (chips, mult) = self.levels.score(hand);
+            fb.text(hand.name(), [61, 70], DrawIndex::Second, DrawIndex::Transparent);
+            fb.text(format_no_std::show(
+                unsafe { FORMAT_BUF.assume_init_mut() },
+                format_args!("L{} {}x{}", self.levels.level(hand), chips, mult),
+            ).unwrap(), [61, 80], DrawIndex::Second, DrawIndex::Transparent);
+        }
 
         self.hand.cards
             .iter()
             .for_each(|card| card.render(fb));
         self.jokers.render(fb);
+        self.consumables.render(fb);
+        self.callouts.render(fb);
+        if self.deck_stats {
+            self.render_deck_stats(fb);
+        }
+    }
+}
+impl Demo {
+    /// What's left to draw: suits, ranks and enhanced cards, over the
+    /// Deck button
+    fn render_deck_stats(&self, fb: &wasm4::draw::Framebuffer) {
+        let mut suits = [0; 4];
+        let mut ranks = [0; Rank::COUNT];
+        let mut enhanced = 0;
+        for card in self.deck.draw_pile() {
+            suits[card.suit() as usize] += 1;
+            ranks[card.rank() as usize] += 1;
+            enhanced += card.enhancement().is_some() as u32;
+        }
+
+        let mut text: heapless::String<128> = heapless::String::new();
+        writeln!(text, "Deck {}/{}", self.deck.remaining(), self.deck.len()).ok();
+        for (suit, count) in "SHCD".chars().zip(suits) {
+            write!(text, "{}:{} ", suit, count).ok();
+        }
+        // Highest first, four to a line
+        for (i, (rank, count)) in "AKQJT98765432".chars().zip(ranks.iter().rev()).enumerate() {
+            let separator = if i % 4 == 0 { '\n' } else { ' ' };
+            write!(text, "{}{}:{}", separator, rank, count).ok();
+        }
+        write!(text, "\nEnhanced: {}", enhanced).ok();
+
+        let width = text.lines().map(|line| line.trim_end().len()).max().unwrap_or(0) as i32 * 8 + 4;
+        let height = text.lines().count() as i32 * 8 + 4;
+        let start = [160 - width, 102 - height];
+        fb.rect(start, [width as u32, height as u32], DrawIndex::Second, DrawIndex::First);
+        fb.text(&text, [start[0] + 2, start[1] + 2], DrawIndex::Fourth, DrawIndex::Transparent);
     }
 }
 
 pub struct HandState {
     size: usize,
-    cards: heapless::Vec<CardState, 10>,
+    cards: heapless::Vec<CardState, HAND_CAPACITY>,
     selected: heapless::Vec<usize, 5>,
+    sort: SortOrder,
+    /// Frame the cards are done sliding into their layout
+    settled: u32,
 }
 impl MessageHandler for HandState {
     fn handle_message(&mut self, rx: &Reader) {
@@ -354,61 +530,182 @@ impl InputHandler for HandState {
     }
 }
 impl HandState {
-    pub fn fill(&mut self, deck: &mut Deck, animation_rng: &mut SmallRng) {
+    pub fn update(&mut self, animation_rng: &mut SmallRng) {
+        self.cards
+            .iter_mut()
+            .for_each(CardState::update);
+
+        if self.cards.iter().any(CardState::is_gone) {
+            self.cards.retain(|card| !card.is_gone());
+            // hand positions shift around, selection would point at the wrong cards
+            self.selected.clear();
+            self.layout(animation_rng);
+        }
+    }
+    /// Brings the hand up to date after the deck was mutated: destroyed cards
+    /// shatter, changed cards are swapped in place and cards added to the
+    /// hand appear in sort order.
+    pub fn sync(&mut self, deck: &Deck, animation_rng: &mut SmallRng) {
+        for state in self.cards.iter_mut() {
+            let CardData::Playing(card) = state.card() else { unreachable!() };
+            match deck.card(card.id()) {
+                None => state.shatter(),
+                Some(current) if current != card => {
+                    state.set_card(CardData::Playing(*current));
+                    state.set_animation(AnimationState::new(
+                        &[Scale::new([0.0, 1.0], [1.0, 1.0]).into()],
+                        Duration::from_secs(0.2),
+                        Some(idle1),
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        let mut added: heapless::Vec<u8, 10> = heapless::Vec::new();
+        for card in deck.hand() {
+            let held = self.cards.iter().any(|state| match state.card() {
+                CardData::Playing(other) => other.id() == card.id(),
+                _ => false,
+            });
+            if held {
+                continue;
+            }
+            let pos = self.cards.len();
+            if self.cards.push(CardState::new(pos, CardData::Playing(*card), [0, 0], None)).is_err() {
+                break;
+            }
+            added.push(card.id()).ok();
+        }
+        if added.is_empty() {
+            return;
+        }
+
+        self.sort_cards();
+        self.layout(animation_rng);
+        for state in self.cards.iter_mut() {
+            if matches!(state.card(), CardData::Playing(card) if added.contains(&card.id())) {
+                state.set_animation(appear());
+            }
+        }
+    }
+    /// Lays the hand out in a new order, cards slide over to their spots
+    pub fn sort(&mut self, sort: SortOrder, animation_rng: &mut SmallRng) {
+        self.sort = sort;
+        self.sort_cards();
+        self.layout(animation_rng);
+    }
+    /// Puts the cards in sort order, the selection follows the cards it
+    /// points at. Positions are only updated by the next layout.
+    fn sort_cards(&mut self) {
+        let selected: heapless::Vec<u8, 5> = self.selected_cards().map(Card::id).collect();
+        let sort = self.sort;
+        self.cards.sort_unstable_by(|left, right| {
+            let (CardData::Playing(left), CardData::Playing(right)) = (left.card(), right.card()) else { unreachable!() };
+            // Only copies of the same card tie, keep those steady
+            sort.cmp(left, right).then(left.id().cmp(&right.id()))
+        });
+        self.selected = selected
+            .iter()
+            .filter_map(|id| self.cards.iter().position(|state| {
+                matches!(state.card(), CardData::Playing(card) if card.id() == *id)
+            }))
+            .collect();
+    }
+    /// Selected cards slide over raised, the selection hold picks them up
+    /// once they're done
+    fn layout(&mut self, animation_rng: &mut SmallRng) {
+        let slide = Duration::from_secs(0.2);
+        self.settled = FrameCounter::get() + slide.as_frames();
         self.cards
             .iter_mut()
             .enumerate()
             .for_each(|(i, card)| {
+                let raise = if self.selected.contains(&i) { -10.0 } else { 0.0 };
                 let old_origin = card.origin();
                 card.set_origin([20 + i as i32 * self.size as i32 * 130 / 60, 140]);
                 card.set_animation(AnimationState::new(
                     &[Translate::new([
                          (old_origin[0] - card.origin()[0]) as f32,
-                         (old_origin[1] - card.origin()[1]) as f32,
-                    ], [0.0, 0.0]).into()],
-                    Duration::from_secs(0.2),
+                         (old_origin[1] - card.origin()[1]) as f32 + raise,
+                    ], [0.0, raise]).into()],
+                    slide,
                     Some(random_idle(animation_rng)),
                 ));
                 card.set_id(i);
             }); 
-        while self.cards.len() != self.size {
+    }
+    pub fn is_settled(&self) -> bool {
+        FrameCounter::get() >= self.settled
+    }
+    /// Draws up to the hand size, new cards are dealt into sort order
+    pub fn fill(&mut self, deck: &mut Deck, animation_rng: &mut SmallRng) {
+        let mut drawn: heapless::Vec<u8, 10> = heapless::Vec::new();
+        while self.cards.len() < self.size {
             match deck.draw() {
                 Some(card) => {
                     let pos = self.cards.len();
-                    let origin = [20 + pos as i32 * self.size as i32 * 130 / 60, 140];
-                    self.cards.push(CardState::new(
-                        pos,
-                        CardData::Playing(card),
-                        origin,
-                        Some(AnimationState::new(
-                            &[
-                                Translate::new(
-                                    [160.0 - origin[0] as f32, 80.0 - origin[1] as f32],
-                                    [0.0, 0.0],
-                                ).into(),
-                                Rotate::new(90.0, 0.0).into(),
-                            ],
-                            Duration::from_frames(animation_rng.next_u32() % 10 + 10),
-                            Some(random_idle(animation_rng)),
-                        )),
-                    )).unwrap();
+                    self.cards.push(CardState::new(pos, CardData::Playing(card), [0, 0], None)).unwrap();
+                    drawn.push(card.id()).ok();
                 }
                 None => break,
             }
         }
-        // self.cards.sort_unstable_by(|left, right| {
-        //     let (CardData::Playing(left), CardData::Playing(right)) = (left.card(), right.card()) else { unreachable!() };
-        //     left.cmp(right)
-        // });
+
+        self.sort_cards();
+        self.layout(animation_rng);
+        // Drawn cards fly in from the deck instead of sliding over
+        for state in self.cards.iter_mut() {
+            if !matches!(state.card(), CardData::Playing(card) if drawn.contains(&card.id())) {
+                continue;
+            }
+            let origin = state.origin();
+            state.set_animation(AnimationState::new(
+                &[
+                    Translate::new(
+                        [160.0 - origin[0] as f32, 80.0 - origin[1] as f32],
+                        [0.0, 0.0],
+                    ).into(),
+                    Rotate::new(90.0, 0.0).into(),
+                ],
+                Duration::from_frames(animation_rng.next_u32() % 10 + 10),
+                Some(random_idle(animation_rng)),
+            ));
+        }
     }
-    fn match_poker(&self) -> BitFlags<PokerHand> {
-        let mut played_cards = self.selected
+    fn selected_cards(&self) -> impl Iterator<Item = &Card> {
+        self.selected
             .iter()
             .map(|idx| {
                 let CardData::Playing(card) = self.cards[*idx].card() else {unreachable!()};
                 card
             })
+    }
+    fn held_cards(&self) -> impl Iterator<Item = &Card> {
+        self.cards
+            .iter()
+            .filter(|card| !self.selected.contains(&card.id()))
+            .map(|state| {
+                let CardData::Playing(card) = state.card() else {unreachable!()};
+                card
+            })
+    }
+    /// Removes the selected cards from the hand, handing them back to be
+    /// put on one of the deck piles.
+    fn take_selected(&mut self) -> heapless::Vec<Card, 5> {
+        let taken = self.selected_cards().cloned().collect();
+        self.cards.retain(|card| !self.selected.contains(&card.id()));
+        self.selected.clear();
+        taken
+    }
+    fn match_poker(&self) -> BitFlags<PokerHand> {
+        // Stone cards score, but take no part in the poker hand
+        let mut played_cards = self.selected_cards()
+            .filter(|card| card.has_rank())
             .collect::<heapless::Vec<_, 5>>();
+        if played_cards.is_empty() {
+            return PokerHand::HighCard.into();
+        }
         played_cards.sort_unstable_by(|left, right| left.rank().cmp(&right.rank()));
         tracef!("played: {:?}", played_cards);
 
@@ -425,7 +722,8 @@ impl HandState {
             }
             current_rank = card.rank();
         }
-        let sets = sets.iter().filter(|i| **i >= 2).collect::<heapless::Vec<_, 2>>();
+        let mut sets = sets.iter().filter(|i| **i >= 2).collect::<heapless::Vec<_, 2>>();
+        sets.sort_unstable_by(|left, right| right.cmp(left));
         tracef!("Sets: {:?}", sets);
 
         let mut matched: BitFlags<PokerHand> = PokerHand::HighCard.into();
@@ -441,13 +739,15 @@ impl HandState {
         };
 
         if played_cards.len() == 5 {
-            // Flush check
-            let mut flush = true;
-            for i in &played_cards {
-                if i.suit() != played_cards[0].suit() {
-                    flush = false;
-                }
-            }
+            // Flush check, Wild cards go with any suit
+            let mut suits = played_cards
+                .iter()
+                .filter(|card| card.enhancement() != Some(Enhancement::Wild))
+                .map(|card| card.suit());
+            let flush = match suits.next() {
+                Some(first) => suits.all(|suit| suit == first),
+                None => true,
+            };
             if flush {
                 matched |= PokerHand::Flush;
             }
@@ -455,7 +755,7 @@ impl HandState {
             // Straight check
             let mut straight = true;
             for (i, card) in played_cards[1..].iter().enumerate() {
-                if card.rank() as u8 != played_cards[i-1].rank() as u8 - 1 {
+                if card.rank() as u8 != played_cards[i].rank() as u8 + 1 {
                     // TODO: Ace, 2... straight detect
                     straight = false;
                 }
@@ -463,6 +763,18 @@ impl HandState {
             if straight {
                 matched |= PokerHand::Straight;
             }
+
+            if matched.contains(PokerHand::Flush) {
+                if matched.contains(PokerHand::Straight) {
+                    matched |= PokerHand::StraightFlush;
+                }
+                if matched.contains(PokerHand::FullHouse) {
+                    matched |= PokerHand::FlushHouse;
+                }
+                if matched.contains(PokerHand::Five) {
+                    matched |= PokerHand::FlushFive;
+                }
+            }
         }
 
         matched
@@ -474,6 +786,102 @@ impl Default for HandState {
             size: 8,
             cards: heapless::Vec::new(),
             selected: heapless::Vec::new(),
+            sort: SortOrder::default(),
+            settled: 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Suit;
+
+    /// A hand with every one of `cards` selected to play
+    pub(super) fn selected(cards: &[Card]) -> HandState {
+        let mut hand = HandState::default();
+        for (i, card) in cards.iter().enumerate() {
+            hand.cards.push(CardState::new(i, CardData::Playing(*card), [0, 0], None)).unwrap();
+            hand.selected.push(i).unwrap();
+        }
+        hand
+    }
+
+    fn best(cards: &[Card]) -> PokerHand {
+        PokerHand::best(selected(cards).match_poker())
+    }
+
+    /// Alternating suits, so no flush gets in the way
+    fn ranks(ranks: &[Rank]) -> heapless::Vec<Card, 5> {
+        ranks
+            .iter()
+            .enumerate()
+            .map(|(i, rank)| Card::new(if i % 2 == 0 { Suit::Spade } else { Suit::Heart }, *rank))
+            .collect()
+    }
+
+    fn enhanced(mut card: Card, enhancement: Enhancement) -> Card {
+        card.enhance(enhancement);
+        card
+    }
+
+    #[test]
+    fn matches_sets() {
+        use Rank::*;
+
+        assert_eq!(best(&ranks(&[Nine])), PokerHand::HighCard);
+        assert_eq!(best(&ranks(&[Two, Three, Five, Five, Nine])), PokerHand::Pair);
+        assert_eq!(best(&ranks(&[Two, Five, Five, Nine, Nine])), PokerHand::TwoPair);
+        assert_eq!(best(&ranks(&[Two, Five, Five, Five, Nine])), PokerHand::Three);
+        assert_eq!(best(&ranks(&[Two, Two, Five, Five, Five])), PokerHand::FullHouse);
+        assert_eq!(best(&ranks(&[King, King, King, King])), PokerHand::Four);
+    }
+
+    #[test]
+    fn stone_cards_take_no_part() {
+        use Rank::*;
+
+        let stone = enhanced(Card::new(Suit::Club, Two), Enhancement::Stone);
+        let mut cards = ranks(&[King, King]);
+        cards.push(stone).unwrap();
+        assert_eq!(best(&cards), PokerHand::Pair);
+        assert_eq!(best(&[stone; 5]), PokerHand::HighCard);
+
+        // Four hearts and a stone are no flush
+        let mut cards: heapless::Vec<Card, 5> = [Two, Five, Seven, Nine]
+            .iter()
+            .map(|rank| Card::new(Suit::Heart, *rank))
+            .collect();
+        cards.push(stone).unwrap();
+        assert_eq!(best(&cards), PokerHand::HighCard);
+    }
+
+    #[test]
+    fn wild_cards_complete_flushes() {
+        use Rank::*;
+
+        let mut cards = [Two, Five, Seven, Nine, Jack].map(|rank| Card::new(Suit::Heart, rank));
+        assert_eq!(best(&cards), PokerHand::Flush);
+
+        cards[4].set_suit(Suit::Spade);
+        assert_eq!(best(&cards), PokerHand::HighCard);
+
+        cards[4] = enhanced(cards[4], Enhancement::Wild);
+        assert_eq!(best(&cards), PokerHand::Flush);
+    }
+
+    #[test]
+    fn matches_straights() {
+        use Rank::*;
+
+        // Played order doesn't matter
+        assert_eq!(best(&ranks(&[Nine, Five, Seven, Six, Eight])), PokerHand::Straight);
+        assert_eq!(best(&ranks(&[Ten, Jack, Queen, King, Ace])), PokerHand::Straight);
+        assert_eq!(best(&ranks(&[Five, Six, Seven, Eight, Ten])), PokerHand::HighCard);
+        // Four in a row is not enough
+        assert_eq!(best(&ranks(&[Five, Six, Seven, Eight])), PokerHand::HighCard);
+
+        let flush = [Five, Six, Seven, Eight, Nine].map(|rank| Card::new(Suit::Club, rank));
+        assert_eq!(best(&flush), PokerHand::StraightFlush);
+    }
+}
@@ -0,0 +1,196 @@
+//! The run kept on the WASM-4 disk. It's written whenever a round ends and
+//! picked back up with the menu's Continue button.
+
+use heapless::Vec;
+use rand::Rng;
+use strum::EnumCount as _;
+use wasm4::sys::{diskr, diskw};
+
+use crate::card::{
+    consumable::Consumable,
+    deck::{Deck, DECK_SAVE_SIZE, HAND_CAPACITY},
+    poker::{HandLevels, PokerHand},
+};
+
+use super::consumables::{Consumables, CONSUMABLES_SAVE_SIZE};
+use super::jokers::{Jokers, JOKERS_SAVE_SIZE};
+use super::Demo;
+
+/// Bumped whenever the layout changes, older saves are ignored
+const VERSION: u8 = 2;
+
+/// Version, money, hands, discards and hand size, hand levels, the
+/// consumables and the last one used, the deck and the jokers, each part at
+/// a fixed offset
+pub const SAVE_SIZE: usize = JOKERS + JOKERS_SAVE_SIZE;
+const MONEY: usize = 1;
+const LIMITS: usize = MONEY + 4;
+const LEVELS: usize = LIMITS + 3;
+const CONSUMABLES: usize = LEVELS + PokerHand::COUNT;
+const LAST_USED: usize = CONSUMABLES + CONSUMABLES_SAVE_SIZE;
+const DECK: usize = LAST_USED + 2;
+const JOKERS: usize = DECK + DECK_SAVE_SIZE;
+
+const _: () = assert!(SAVE_SIZE <= 1024, "WASM-4 disks hold 1 KiB");
+
+/// Kind byte of a missing last used consumable
+const NONE_USED: u8 = 0xFF;
+
+/// Everything a run keeps between rounds
+pub struct Save {
+    pub deck: Deck,
+    pub jokers: Jokers,
+    pub consumables: Consumables,
+    pub levels: HandLevels,
+    pub money: u32,
+    pub max_hands: u8,
+    pub max_discards: u8,
+    pub hand_size: usize,
+    pub last_used: Option<Consumable>,
+}
+
+pub fn encode(demo: &Demo) -> Vec<u8, SAVE_SIZE> {
+    let mut bytes = Vec::new();
+    bytes.push(VERSION).unwrap();
+    bytes.extend_from_slice(&demo.money.to_le_bytes()).unwrap();
+    bytes.extend_from_slice(&[demo.max_hands, demo.max_discards, demo.hand.size as u8]).unwrap();
+    bytes.extend_from_slice(&demo.levels.save()).unwrap();
+    bytes.extend_from_slice(&demo.consumables.save()).unwrap();
+    // Unused space is padded so every part starts at its offset
+    bytes.resize(LAST_USED, 0).unwrap();
+    let last_used = demo.last_used.map_or([NONE_USED, 0], Consumable::to_bytes);
+    bytes.extend_from_slice(&last_used).unwrap();
+    bytes.extend_from_slice(&demo.deck.save()).unwrap();
+    bytes.resize(JOKERS, 0).unwrap();
+    bytes.extend_from_slice(&demo.jokers.save()).unwrap();
+    bytes
+}
+
+/// `None` for an empty disk, an older version or a corrupted save
+pub fn decode<T: Rng>(bytes: &[u8], rng: &mut T) -> Option<Save> {
+    if *bytes.first()? != VERSION {
+        return None;
+    }
+    let money = u32::from_le_bytes(bytes.get(MONEY..LIMITS)?.try_into().ok()?);
+    let [max_hands, max_discards, hand_size] = bytes.get(LIMITS..LEVELS)?.try_into().ok()?;
+    if !(1..=HAND_CAPACITY).contains(&(hand_size as usize)) {
+        return None;
+    }
+    let levels = HandLevels::load(bytes.get(LEVELS..CONSUMABLES)?)?;
+    let consumables = Consumables::load(bytes.get(CONSUMABLES..LAST_USED)?)?;
+    let last_used = match bytes.get(LAST_USED..DECK)? {
+        [NONE_USED, _] => None,
+        used => Some(Consumable::from_bytes(used.try_into().ok()?)?),
+    };
+    let deck = Deck::load(bytes.get(DECK..JOKERS)?, rng)?;
+    let jokers = Jokers::load(&bytes[JOKERS..])?;
+    Some(Save {
+        deck,
+        jokers,
+        consumables,
+        levels,
+        money,
+        max_hands,
+        max_discards,
+        hand_size: hand_size as usize,
+        last_used,
+    })
+}
+
+pub fn store(demo: &Demo) {
+    let bytes = encode(demo);
+    unsafe { diskw(bytes.as_ptr(), bytes.len() as u32) };
+}
+
+pub fn restore<T: Rng>(rng: &mut T) -> Option<Save> {
+    let mut buf = [0; SAVE_SIZE];
+    let len = unsafe { diskr(buf.as_mut_ptr(), SAVE_SIZE as u32) };
+    decode(&buf[..len as usize], rng)
+}
+
+/// Whether there's a run to continue, without rebuilding it
+pub fn exists() -> bool {
+    let mut version = 0;
+    unsafe { diskr(&mut version, 1) == 1 && version == VERSION }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::card::{
+        consumable::{Planet, Spectral, Tarot},
+        deck::DeckType,
+        joker::{Joker, JokerType},
+        state::CardData,
+    };
+
+    /// A run some rounds in, with something in every part of the save
+    fn played() -> Demo {
+        let mut demo = Demo::new(DeckType::Erratic);
+        demo.money = 42;
+        demo.max_hands = 5;
+        demo.max_discards = 2;
+        demo.hand.size = 7;
+        demo.levels.level_up(PokerHand::Flush);
+        demo.levels.level_up(PokerHand::Flush);
+        demo.consumables = Consumables::default();
+        demo.consumables.add(CardData::Planet(Planet::Saturn)).unwrap();
+        demo.consumables.add(CardData::Spectral(Spectral::Ankh)).unwrap();
+        demo.last_used = Some(Consumable::Tarot(Tarot::Hermit));
+        demo.jokers.add(Joker::new(JokerType::Egg)).unwrap();
+        demo
+    }
+
+    #[test]
+    fn round_trip() {
+        let demo = played();
+        let mut rng = SmallRng::seed_from_u64(7);
+        let save = decode(&encode(&demo), &mut rng).unwrap();
+
+        assert_eq!(save.money, 42);
+        assert_eq!((save.max_hands, save.max_discards, save.hand_size), (5, 2, 7));
+        assert_eq!(save.levels.save(), demo.levels.save());
+        assert_eq!(save.levels.level(PokerHand::Flush), 3);
+        assert_eq!(save.consumables.save(), demo.consumables.save());
+        assert_eq!(save.consumables.get(1), Some(Consumable::Spectral(Spectral::Ankh)));
+        assert_eq!(save.last_used, Some(Consumable::Tarot(Tarot::Hermit)));
+        assert_eq!(save.deck.kind(), DeckType::Erratic);
+        assert_eq!(save.deck.len(), demo.deck.len());
+        assert_eq!(save.jokers.save(), demo.jokers.save());
+
+        // Nothing used yet
+        let fresh = Demo::new(DeckType::Red);
+        assert_eq!(decode(&encode(&fresh), &mut rng).unwrap().last_used, None);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut bytes = encode(&played());
+        bytes[0] = VERSION - 1;
+
+        assert!(decode(&bytes, &mut rng).is_none());
+        assert!(decode(&[], &mut rng).is_none());
+    }
+
+    #[test]
+    fn rejects_corrupted_run_state() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let bytes = encode(&played());
+
+        let mut no_hand = bytes.clone();
+        no_hand[LIMITS + 2] = 0;
+        assert!(decode(&no_hand, &mut rng).is_none());
+
+        let mut level_zero = bytes.clone();
+        level_zero[LEVELS] = 0;
+        assert!(decode(&level_zero, &mut rng).is_none());
+
+        // Three consumables in two slots
+        let mut overfull = bytes.clone();
+        overfull[CONSUMABLES] = 3;
+        assert!(decode(&overfull, &mut rng).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use heapless::Vec;
+use rand::Rng as _;
+
+use crate::card::{
+    joker::{Effect, GameView, JokerEvent, NumberEffect},
+    poker::PokerHand,
+    Card, Edition, Enhancement, Seal,
+};
+
+use super::Demo;
+
+/// Most times a single card can be triggered by one hand
+const MAX_TRIGGERS: u8 = 4;
+
+pub struct Score {
+    pub points: f32,
+    pub mult: f32,
+}
+impl Score {
+    pub fn balance(&mut self) {
+        let average = (self.points + self.mult) / 2.0;
+        self.points = average;
+        self.mult = average;
+    }
+    pub fn total(&self) -> u32 {
+        (self.points * self.mult) as u32
+    }
+    pub fn apply(&mut self, effect: &NumberEffect) {
+        self.points += effect.points as f32;
+        self.mult += effect.mult as f32;
+        self.mult *= effect.multx;
+    }
+}
+impl From<(f32, f32)> for Score {
+    fn from(value: (f32, f32)) -> Self {
+        Self { points: value.0, mult: value.1 }
+    }
+}
+
+/// Numbers go to the score, money is paid out right away
+fn resolve(effect: Effect, score: &mut Score, money: &mut u32) {
+    match effect {
+        Effect::Numbers(numbers) => score.apply(&numbers),
+        Effect::Money(amount) => *money += amount,
+        // Only happens on events, never while scoring
+        Effect::Destroy => (),
+    }
+}
+
+impl Demo {
+    /// Lets every joker reacting to `event` fire, left to right
+    pub(super) fn trigger(&mut self, event: JokerEvent) {
+        let held: Vec<Card, 16> = self.hand.held_cards().copied().collect();
+        let view = GameView {
+            hand: self.last_played.unwrap_or(PokerHand::HighCard),
+            contained: Default::default(),
+            played: &[],
+            held: &held,
+            deck_size: self.deck.remaining(),
+            money: self.money,
+            discards: self.rem_discards,
+            hands: self.rem_hands,
+        };
+        let mut destroyed: Vec<usize, 10> = Vec::new();
+        for (pos, joker) in self.jokers.iter_mut() {
+            match joker.on_event(&event, &view) {
+                Some(Effect::Money(money)) => self.money += money,
+                Some(Effect::Destroy) => destroyed.push(pos).unwrap(),
+                // Nothing is being scored
+                Some(Effect::Numbers(_)) | None => (),
+            }
+        }
+        destroyed.into_iter().for_each(|pos| self.jokers.destroy(pos));
+    }
+
+    /// Scores the selected cards. Every scored card is followed by the jokers
+    /// reacting to it, left to right, and the whole chain runs again for
+    /// every retrigger. Then cards held in hand count and finally every joker
+    /// gets its independent effect, again left to right.
+    pub(super) fn score_hand(&mut self) -> Score {
+        let contained = self.hand.match_poker();
+        let hand = PokerHand::best(contained);
+        let played: Vec<Card, 5> = self.hand.selected_cards().copied().collect();
+        let held: Vec<Card, 16> = self.hand.held_cards().copied().collect();
+        let view = GameView {
+            hand,
+            contained,
+            played: &played,
+            held: &held,
+            deck_size: self.deck.remaining(),
+            money: self.money,
+            discards: self.rem_discards,
+            hands: self.rem_hands,
+        };
+
+        let mut score: Score = self.levels.score(hand).into();
+        self.last_played = Some(hand);
+
+        let origins: Vec<[i32; 2], 5> = self.hand.selected
+            .iter()
+            .map(|i| self.hand.cards[*i].origin())
+            .collect();
+
+        for (pos, card) in played.iter().enumerate() {
+            let retriggers = (card.seal() == Some(Seal::Red)) as u8 + self.jokers
+                .iter()
+                .filter_map(|(i, _)| self.jokers.ability(i))
+                .map(|joker| joker.retriggers(card, pos, &view))
+                .sum::<u8>();
+            let triggers = (1 + retriggers).min(MAX_TRIGGERS);
+
+            for trigger in 0..triggers {
+                if trigger > 0 {
+                    self.callouts.push("Again!", [origins[pos][0], origins[pos][1] - 25]);
+                }
+                self.trigger_card(card, origins[pos], &view, &mut score);
+            }
+        }
+
+        for card in &held {
+            if card.enhancement() == Some(Enhancement::Steel) {
+                score.mult *= 1.5;
+            }
+        }
+
+        for (pos, joker) in self.jokers.iter() {
+            // Foil and holographic add before the joker's own effect,
+            // polychrome multiplies after it
+            let (before, after) = match joker.edition() {
+                Some(Edition::Foil) => (Some(Effect::chips(50)), None),
+                Some(Edition::Holographic) => (Some(Effect::mult(10)), None),
+                Some(Edition::Polychrome) => (None, Some(Effect::multx(1.5))),
+                _ => (None, None),
+            };
+            // Copy jokers resolve the copied joker's effect with its state
+            let effect = self.jokers.ability(pos).and_then(|source| source.independent(&view));
+            let [x, y] = self.jokers.jokers[pos].origin();
+            for effect in [before, effect, after].into_iter().flatten() {
+                resolve(effect, &mut score, &mut self.money);
+                self.callouts.push_effect(&effect, [x, y - 25], Some(pos));
+            }
+        }
+
+        score
+    }
+
+    /// One full trigger of a scored card: its own chips and bonuses, then
+    /// the jokers reacting to it
+    fn trigger_card(&mut self, card: &Card, origin: [i32; 2], view: &GameView, score: &mut Score) {
+        score.points += card.chips() as f32;
+        match card.enhancement() {
+            Some(Enhancement::Mult) => score.mult += 4.0,
+            Some(Enhancement::Glass) => {
+                score.mult *= 2.0;
+                if self.rng.gen_range(0..4) == 0 && !self.broken.contains(&card.id()) {
+                    self.broken.push(card.id()).ok();
+                }
+            }
+            Some(Enhancement::Lucky) if self.rng.gen_range(0..5) == 0 => score.mult += 20.0,
+            _ => (),
+        }
+        match card.edition() {
+            Some(Edition::Foil) => score.points += 50.0,
+            Some(Edition::Holographic) => score.mult += 10.0,
+            Some(Edition::Polychrome) => score.mult *= 1.5,
+            _ => (),
+        }
+        if card.seal() == Some(Seal::Gold) {
+            let gold = Effect::Money(3);
+            resolve(gold, score, &mut self.money);
+            self.callouts.push_effect(&gold, [origin[0], origin[1] - 25], None);
+        }
+
+        for (pos, _) in self.jokers.iter() {
+            if let Some(effect) = self.jokers.ability(pos).and_then(|source| source.on_card(card, view)) {
+                resolve(effect, score, &mut self.money);
+                let [x, y] = self.jokers.jokers[pos].origin();
+                self.callouts.push_effect(&effect, [x, y - 25], Some(pos));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{deck::DeckType, joker::{Joker, JokerType, Sticker}, Rank, Suit};
+    use crate::scene::demo::{jokers::Jokers, tests::selected};
+
+    /// A run with `cards` selected and only `jokers` in play
+    fn demo(cards: &[Card], jokers: &[Joker]) -> Demo {
+        let mut demo = Demo::new(DeckType::default());
+        demo.jokers = Jokers::empty();
+        jokers.iter().for_each(|joker| demo.jokers.add(joker.clone()).unwrap());
+        demo.hand = selected(cards);
+        demo
+    }
+
+    fn score(cards: &[Card], jokers: &[JokerType]) -> Score {
+        let jokers: heapless::Vec<Joker, 5> = jokers.iter().map(|kind| Joker::new(*kind)).collect();
+        demo(cards, &jokers).score_hand()
+    }
+
+    #[test]
+    fn red_seals_retrigger() {
+        let mut ace = Card::new(Suit::Spade, Rank::Ace);
+        // High card, 5 chips and 1 mult
+        assert_eq!(score(&[ace], &[]).total(), 5 + 11);
+
+        ace.set_seal(Seal::Red);
+        assert_eq!(score(&[ace], &[]).total(), 5 + 11 * 2);
+    }
+
+    #[test]
+    fn retriggers_add_up() {
+        let mut two = Card::new(Suit::Spade, Rank::Two);
+        assert_eq!(score(&[two], &[JokerType::Hack]).total(), 5 + 2 * 2);
+        assert_eq!(score(&[two], &[JokerType::Hack, JokerType::HangingChad]).total(), 5 + 2 * 3);
+
+        two.set_seal(Seal::Red);
+        let triggers = score(&[two], &[JokerType::Hack, JokerType::Hack, JokerType::HangingChad]);
+        assert_eq!(triggers.total(), 5 + 2 * MAX_TRIGGERS as u32);
+    }
+
+    #[test]
+    fn retriggers_follow_the_card() {
+        use Rank::*;
+
+        // Only the first scored card gets to go again
+        let cards = [Nine, Nine].map(|rank| Card::new(Suit::Heart, rank));
+        let plain = score(&cards, &[]);
+        let chad = score(&cards, &[JokerType::HangingChad]);
+        assert_eq!(chad.points - plain.points, 9.0);
+    }
+
+    #[test]
+    fn melted_ice_cream_adds_nothing() {
+        let ace = Card::new(Suit::Spade, Rank::Ace);
+        assert_eq!(score(&[ace], &[JokerType::IceCream]).total(), 5 + 11 + 100);
+
+        // Eternal, so it stays around after melting
+        let mut melted = Joker::from_bytes([JokerType::IceCream as u8, 0, 20, 0, 0, 0]).unwrap();
+        melted.add_sticker(Sticker::Eternal);
+        let mut demo = demo(&[ace], &[melted]);
+        assert_eq!(demo.score_hand().total(), 5 + 11);
+        // Not even a +0 callout
+        assert!(demo.callouts.is_empty());
+    }
+}
@@ -2,18 +2,23 @@ use core::mem::MaybeUninit;
 
 use crate::{
     button::Button,
-    card::{animations::idle1, state::{CardData, CardState}, Card, Rank, Suit},
+    card::{animations::idle1, deck::DeckType, state::{CardData, CardState}, Card, Rank, Suit},
     gfx::Render,
     message::{InputHandler, Message, MessageHandler, Reader, Writer},
     MouseCompound,
 };
 
-use super::Scene;
+use super::{save, Scene};
 
 static mut MENU: MaybeUninit<Menu> = MaybeUninit::uninit();
 pub struct Menu {
     ace: CardState,
     start: Button,
+    // Only there with a run on disk
+    resume: Option<Button>,
+
+    deck_type: DeckType,
+    deck: CardState,
 }
 impl Menu {
     pub fn init() {
@@ -22,22 +27,39 @@ impl Menu {
         }
     }
     fn new() -> Self {
+        let deck_type = DeckType::default();
         Self {
             ace: CardState::new(
                 0,
                 CardData::Playing(Card::new(Suit::Spade, Rank::Ace)),
-                [80, 60],
+                [45, 60],
                 Some(idle1()),
             ),
-            start: Button::new(
-                [30, 100],
-                "Start",
+            start: Self::start_button(deck_type),
+            resume: save::exists().then(|| Button::new(
+                [22, 115],
+                "Continue",
                 wasm4::draw::DrawIndex::Third,
                 wasm4::draw::DrawIndex::Second,
-                Message::Start,
-            ),
+                Message::Continue,
+            )),
+
+            deck_type,
+            deck: Self::deck_card(deck_type),
         }
     }
+    fn start_button(deck_type: DeckType) -> Button {
+        Button::new(
+            [30, 100],
+            "Start",
+            wasm4::draw::DrawIndex::Third,
+            wasm4::draw::DrawIndex::Second,
+            Message::Start(deck_type),
+        )
+    }
+    fn deck_card(deck_type: DeckType) -> CardState {
+        CardState::new(1, CardData::Back(deck_type), [115, 60], Some(idle1()))
+    }
     pub fn get() -> &'static mut Self {
         unsafe {
             MENU.assume_init_mut()
@@ -47,22 +69,50 @@ impl Menu {
 impl MessageHandler for Menu {
     fn handle_message(&mut self, rx: &Reader) {
         self.ace.handle_message(rx);
+        self.deck.handle_message(rx);
+
+        // Clicking the deck back cycles through the starting decks
+        match rx.read() {
+            Some(Message::CardClicked(id)) if id == self.deck.id() => {
+                self.deck_type = self.deck_type.next();
+                self.deck = Self::deck_card(self.deck_type);
+                self.start = Self::start_button(self.deck_type);
+            }
+            _ => (),
+        }
     }
 }
 impl InputHandler for Menu {
     fn handle_input(&self, mouse: &MouseCompound, tx: &mut Writer) {
         self.ace.handle_input(mouse, tx);
+        self.deck.handle_input(mouse, tx);
         self.start.handle_input(mouse, tx);
+        if let Some(resume) = &self.resume {
+            resume.handle_input(mouse, tx);
+        }
     }
 }
 impl Scene for Menu {
     fn update(&mut self) {
         self.ace.update();
+        self.deck.update();
     }
 }
 impl Render for Menu {
     fn render(&self, fb: &wasm4::draw::Framebuffer) {
         self.ace.render(fb);
+        self.deck.render(fb);
         self.start.render(fb);
+        if let Some(resume) = &self.resume {
+            resume.render(fb);
+        }
+
+        let name = self.deck_type.name();
+        fb.text(
+            name,
+            [115 - name.len() as i32 * 4, 86],
+            wasm4::draw::DrawIndex::Second,
+            wasm4::draw::DrawIndex::Transparent,
+        );
     }
 }